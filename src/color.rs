@@ -1,5 +1,36 @@
 use crate::term_colors::TERM_COLORS;
-use std::fmt;
+use std::{fmt, fs, sync::OnceLock};
+
+// Colors of the terminal's palette loaded with --palette-file, TERM_COLORS otherwise
+static PALETTE: OnceLock<Vec<Color>> = OnceLock::new();
+
+// Palette the colors are matched against on terminals without true colors
+fn palette() -> &'static [Color] {
+	PALETTE.get().map_or(&TERM_COLORS, |p| p.as_slice())
+}
+
+// Load the terminal's palette from a JSON array of "#rrggbb" strings or [r, g, b] triples
+// The first 16 colors at least are required, the missing ones keep their default values
+pub fn load_palette(path: &str) {
+	let contents = fs::read_to_string(path).unwrap_or_else(|_| panic!("Can't open the palette {}.", path));
+	let parsed = jzon::parse(&contents).unwrap_or_else(|_| panic!("{} is not a valid palette.", path));
+	
+	let mut colors = TERM_COLORS.to_vec();
+	let entries: Vec<&jzon::JsonValue> = parsed.members().collect();
+	if entries.len() < 16 || entries.len() > 256 {
+		panic!("{} must have between 16 and 256 colors, it has {}.", path, entries.len());
+	}
+	for (i, entry) in entries.iter().enumerate() {
+		let rgb: Vec<Option<u8>> = entry.members().map(|c| c.as_u8()).collect();
+		colors[i] = match (entry.as_str(), rgb.as_slice()) {
+			(Some(hex), _) => Color::parse_hex(hex),
+			(None, &[Some(r), Some(g), Some(b)]) => Some(Color::new_rgb(r, g, b)),
+			_ => None
+		}.unwrap_or_else(|| panic!("Color {} of {} must be \"#rrggbb\" or [r, g, b].", i, path));
+	}
+	
+	PALETTE.set(colors).unwrap();
+}
 
 pub enum TermColorSupport {
 	TrueColor,
@@ -44,6 +75,23 @@ impl Color {
 		}
 	}
 	
+	// Red, green and blue components
+	pub const fn rgb(self) -> (u8, u8, u8) {
+		(self.r, self.g, self.b)
+	}
+	
+	pub const fn r(self) -> u8 {
+		self.r
+	}
+	
+	pub const fn g(self) -> u8 {
+		self.g
+	}
+	
+	pub const fn b(self) -> u8 {
+		self.b
+	}
+	
 	// New color from HSV (Hue is in degrees, saturation and value range from 0.0 to 1.0) (https://en.wikipedia.org/wiki/HSL_and_HSV#Color_conversion_formulae)
 	pub fn new_hsv(hue: f32, saturation: f32, value: f32) -> Color {
 		let c = value * saturation;
@@ -65,47 +113,138 @@ impl Color {
 		}
 	}
 	
+	// New color from HSL (Hue is in degrees, saturation and lightness range from 0.0 to 1.0) (https://en.wikipedia.org/wiki/HSL_and_HSV#HSL_to_RGB)
+	pub fn new_hsl(hue: f32, saturation: f32, lightness: f32) -> Color {
+		let c = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+		let x = c * (1.0 - (((hue / 60.0) % 2.0) - 1.0).abs());
+		let m = lightness - c / 2.0;
+		
+		let nc = ((c + m) * 255.0).round() as u8;
+		let nx = ((x + m) * 255.0).round() as u8;
+		let no = (m * 255.0).round() as u8;
+		
+		match hue {
+			h if (0.0..60.0).contains(&h) => Color{r: nc, g: nx, b: no},
+			h if (60.0..120.0).contains(&h) => Color{r: nx, g: nc, b: no},
+			h if (120.0..180.0).contains(&h) => Color{r: no, g: nc, b: nx},
+			h if (180.0..240.0).contains(&h) => Color{r: no, g: nx, b: nc},
+			h if (240.0..300.0).contains(&h) => Color{r: nx, g: no, b: nc},
+			h if (300.0..360.0).contains(&h) => Color{r: nc, g: no, b: nx},
+			_ => panic!("Hue must be between 0 and 360"),
+		}
+	}
+	
+	// Hue in degrees, with the largest and smallest components from 0.0 to 1.0, greys have a hue of 0 (https://en.wikipedia.org/wiki/HSL_and_HSV#General_approach)
+	fn hue(self) -> (f32, f32, f32) {
+		let (r, g, b) = (self.r as f32 / 255.0, self.g as f32 / 255.0, self.b as f32 / 255.0);
+		let max = r.max(g).max(b);
+		let min = r.min(g).min(b);
+		let d = max - min;
+		
+		let hue = if d == 0.0 {
+			0.0
+		} else if max == r {
+			60.0 * ((g - b) / d).rem_euclid(6.0)
+		} else if max == g {
+			60.0 * ((b - r) / d + 2.0)
+		} else {
+			60.0 * ((r - g) / d + 4.0)
+		};
+		(hue, max, min)
+	}
+	
+	// Hue in degrees, saturation and value from 0.0 to 1.0, the inverse of new_hsv
+	pub fn to_hsv(self) -> (f32, f32, f32) {
+		let (hue, max, min) = self.hue();
+		let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+		(hue, saturation, max)
+	}
+	
+	// Hue in degrees, saturation and lightness from 0.0 to 1.0, the inverse of new_hsl
+	pub fn to_hsl(self) -> (f32, f32, f32) {
+		let (hue, max, min) = self.hue();
+		let lightness = (max + min) / 2.0;
+		let saturation = if max == min { 0.0 } else { (max - min) / (1.0 - (2.0 * lightness - 1.0).abs()) };
+		(hue, saturation, lightness)
+	}
+	
 	// New color from hex string
 	pub fn new_hex(hex: &str) -> Color {
+		Color::parse_hex(hex).unwrap_or_else(|| panic!("{} is not a valid hex color.", hex))
+	}
+	
+	// New color from a hex string like #rrggbb, None if it isn't one
+	pub fn parse_hex(hex: &str) -> Option<Color> {
 		let hex = hex.trim_start_matches("#");
-		Color {
-			r: u8::from_str_radix(&hex[0..2], 16).unwrap(),
-			g: u8::from_str_radix(&hex[2..4], 16).unwrap(),
-			b: u8::from_str_radix(&hex[4..6], 16).unwrap()
+		if hex.len() != 6 || !hex.is_ascii() {
+			return None;
 		}
+		Some(Color {
+			r: u8::from_str_radix(&hex[0..2], 16).ok()?,
+			g: u8::from_str_radix(&hex[2..4], 16).ok()?,
+			b: u8::from_str_radix(&hex[4..6], 16).ok()?
+		})
 	}
 	
 	// Convert color to hex
-	pub fn to_hex(&self) -> String {
+	pub fn to_hex(self) -> String {
 		format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
 	}
 	
+	// Mix other over self, alpha going from 0.0 (only self) to 1.0 (only other)
+	pub fn blend(self, other: Color, alpha: f32) -> Color {
+		let alpha = alpha.clamp(0.0, 1.0);
+		let mix = |a: u8, b: u8| (a as f32 * (1.0 - alpha) + b as f32 * alpha).round() as u8;
+		Color {
+			r: mix(self.r, other.r),
+			g: mix(self.g, other.g),
+			b: mix(self.b, other.b)
+		}
+	}
+	
 	// Find the closest color from a list of colors
-	fn closest_color(&self, colors: &[Color]) -> (Color, usize) {
+	fn closest_color(self, colors: &[Color]) -> (Color, usize) {
 		let mut best = 0;
-		let mut best_score = std::f32::MAX;
-		for i in 1..colors.len() {
+		let mut best_score = f32::MAX;
+		for (i, color) in colors.iter().enumerate() {
 			// Distance in RGB space (lower = better match) // TODO: Fix this
-			let score = ((colors[i].r as f32 - self.r as f32).powf(2.0) + (colors[i].g as f32 - self.g as f32).powf(2.0) + (colors[i].b as f32 - self.b as f32).powf(2.0)).sqrt();
+			let score = ((color.r as f32 - self.r as f32).powf(2.0) + (color.g as f32 - self.g as f32).powf(2.0) + (color.b as f32 - self.b as f32).powf(2.0)).sqrt();
 			if score < best_score {
 				best = i;
 				best_score = score;
 			}
 		}
-		(colors[best].clone(), best)
+		(colors[best], best)
 	}
 	
 	// Convert color to escape sequence
-	pub fn to_escape(&self, term_color_support: &TermColorSupport) -> Option<String> {
+	pub fn to_escape(self, term_color_support: &TermColorSupport) -> Option<String> {
 		match term_color_support {
 			TermColorSupport::TrueColor => Some(format!("\x1b[38;2;{};{};{}m", self.r, self.g, self.b)),
 			TermColorSupport::Ansi256 => {
-				let c_index = self.closest_color(&TERM_COLORS).1;
+				let c_index = self.closest_color(palette()).1;
 				Some(format!("\x1b[38;5;{}m", c_index))
 			},
 			TermColorSupport::Ansi16 => {
-				let c_index = self.closest_color(&TERM_COLORS[0..16]).1;
-				let prefix = if c_index > 7 {3} else {9};
+				let c_index = self.closest_color(&palette()[0..16]).1;
+				let prefix = if c_index > 7 {9} else {3};
+				Some(format!("\x1b[{}{}m", prefix, c_index % 8))
+			},
+			TermColorSupport::None => None
+		}
+	}
+	
+	// Convert color to background escape sequence
+	pub fn to_bg_escape(self, term_color_support: &TermColorSupport) -> Option<String> {
+		match term_color_support {
+			TermColorSupport::TrueColor => Some(format!("\x1b[48;2;{};{};{}m", self.r, self.g, self.b)),
+			TermColorSupport::Ansi256 => {
+				let c_index = self.closest_color(palette()).1;
+				Some(format!("\x1b[48;5;{}m", c_index))
+			},
+			TermColorSupport::Ansi16 => {
+				let c_index = self.closest_color(&palette()[0..16]).1;
+				let prefix = if c_index > 7 {10} else {4};
 				Some(format!("\x1b[{}{}m", prefix, c_index % 8))
 			},
 			TermColorSupport::None => None
@@ -113,11 +252,13 @@ impl Color {
 	}
 }
 
+#[derive(Default, Clone)]
 pub struct ColoredText {
 	chars: Vec<char>,
 	colors: Vec<Color>,
 	underline: Vec<bool>,
 	bold: Vec<bool>,
+	backgrounds: Vec<Option<Color>>, // None keeps the background of the pixels
 }
 
 impl ColoredText {
@@ -128,6 +269,7 @@ impl ColoredText {
 			colors: Vec::new(),
 			underline: Vec::new(),
 			bold: Vec::new(),
+			backgrounds: Vec::new(),
 		}
 	}
 	
@@ -150,6 +292,20 @@ impl ColoredText {
 		self.colors.clone()
 	}
 	
+	// Set the color of the character at index
+	pub fn set_color(&mut self, idx: usize, color: Color) {
+		if idx < self.colors.len() {
+			self.colors[idx] = color;
+		}
+	}
+	
+	// Set the character at index
+	pub fn set_char(&mut self, idx: usize, ch: char) {
+		if idx < self.chars.len() {
+			self.chars[idx] = ch;
+		}
+	}
+	
 	// Get underline status
 	pub fn underline(&self) -> Vec<bool> {
 		self.underline.clone()
@@ -167,11 +323,28 @@ impl ColoredText {
 		self.bold.clone()
 	}
 	
+	// Get background colors
+	pub fn backgrounds(&self) -> Vec<Option<Color>> {
+		self.backgrounds.clone()
+	}
+	
+	// Set the background color of the character at index
+	pub fn set_background(&mut self, idx: usize, color: Option<Color>) {
+		if idx < self.backgrounds.len() {
+			self.backgrounds[idx] = color;
+		}
+	}
+	
 	// Get text length
 	pub fn len(&self) -> usize {
 		self.chars.len()
 	}
 	
+	// Whether there are no characters
+	pub fn is_empty(&self) -> bool {
+		self.chars.is_empty()
+	}
+	
 	// Get characters as a String
 	pub fn text(&self) -> String {
 		let mut s = String::new();
@@ -183,6 +356,19 @@ impl ColoredText {
 		s
 	}
 	
+	// Get the characters between two indexes, the end being clamped to the length
+	pub fn slice(&self, start: usize, end: usize) -> ColoredText {
+		let end = end.min(self.len());
+		let start = start.min(end);
+		ColoredText {
+			chars: self.chars[start..end].to_vec(),
+			colors: self.colors[start..end].to_vec(),
+			underline: self.underline[start..end].to_vec(),
+			bold: self.bold[start..end].to_vec(),
+			backgrounds: self.backgrounds[start..end].to_vec(),
+		}
+	}
+	
 	// Split text into a vector of Strings by a character
 	pub fn split(&self, pattern_char: char) -> Vec<String> {
 		let mut res: Vec<String> = Vec::new();
@@ -196,6 +382,7 @@ impl ColoredText {
 				current.push(c);
 			}
 		}
+		res.push(current);
 		
 		res
 	}
@@ -206,6 +393,7 @@ impl ColoredText {
 		self.colors.push(color);
 		self.underline.push(underline);
 		self.bold.push(bold);
+		self.backgrounds.push(None);
 	}
 	
 	// Insert character at index
@@ -214,11 +402,13 @@ impl ColoredText {
 		self.colors.insert(idx, color);
 		self.underline.insert(idx, underline);
 		self.bold.insert(idx, bold);
+		self.backgrounds.insert(idx, None);
 	}
 	
 	// Remove last character
 	pub fn pop(&mut self) -> Option<(char, Color, bool, bool)> {
-		if self.len() > 0 {
+		if !self.is_empty() {
+			self.backgrounds.pop();
 			Some((self.chars.pop().unwrap(), self.colors.pop().unwrap(), self.underline.pop().unwrap(), self.bold.pop().unwrap()))
 		} else {
 			None
@@ -232,94 +422,129 @@ impl ColoredText {
 			self.colors.push(color);
 			self.underline.push(underline);
 			self.bold.push(bold);
+			self.backgrounds.push(None);
 		}
 	}
 	
 	// Add line returns to wrap text to a given max width
 	pub fn word_wrap(&mut self, width: usize) {
-		let mut line_length = 0;
-		let mut last_word_start = 0;
-		
-		let chars: Vec<char> = self.chars().clone();
-		let colors: Vec<Color> = self.colors().clone();
-		let underline: Vec<bool> = self.underline.clone();
-		let bold: Vec<bool> = self.bold.clone();
+		let breaks = wrap_indices(&self.chars, width);
+		let old = std::mem::take(self);
 		
-		let mut new_chars = Vec::new();
-		let mut new_colors = Vec::new();
-		let mut new_underline = Vec::new();
-		let mut new_bold = Vec::new();
-		
-		//TODO: Make this less gross
-		for i in 0..chars.len() {
-			match chars[i] {
-				'\n' => {
-					new_chars.push('\n');
-					new_colors.push(colors[i]);
-					new_underline.push(underline[i]);
-					new_bold.push(bold[i]);
-					line_length = 0;
-				},
-				' ' => {
-					new_chars.push(' ');
-					new_colors.push(colors[i]);
-					new_underline.push(underline[i]);
-					new_bold.push(bold[i]);
-					last_word_start = new_chars.len();
-					line_length += 1;
-				},
-				_ => {
-					if line_length + 1 > width  {
-						if new_chars.len() - last_word_start >= width {
-							new_chars.push('\n');
-							new_colors.push(Color::new_rgb(255, 255, 255));
-							new_underline.push(false);
-							new_bold.push(false);
-							last_word_start = new_chars.len();
-							new_chars.push(chars[i]);
-							new_colors.push(colors[i]);
-							new_underline.push(underline[i]);
-							new_bold.push(bold[i]);
-							line_length = 1;
-						} else {
-							while new_chars[last_word_start - 1].is_whitespace() { // No whitespace on newlines
-								new_chars.remove(last_word_start - 1);
-								new_colors.remove(last_word_start - 1);
-								new_underline.remove(last_word_start - 1);
-								new_bold.remove(last_word_start - 1);
-								last_word_start -= 1;
-							}
-							new_chars.insert(last_word_start, '\n');
-							new_colors.insert(last_word_start, Color::new_rgb(255, 255, 255));
-							new_underline.insert(last_word_start, false);
-							new_bold.insert(last_word_start, false);
-							last_word_start += 1;
-							new_chars.push(chars[i]);
-							new_colors.push(colors[i]);
-							new_underline.push(underline[i]);
-							new_bold.push(bold[i]);
-							line_length = new_chars.len() - last_word_start;
-						}
-					} else {
-						new_chars.push(chars[i]);
-						new_colors.push(colors[i]);
-						new_underline.push(underline[i]);
-						new_bold.push(bold[i]);
-						line_length += 1;
-					}
+		let mut next_break = breaks.iter().peekable();
+		for i in 0..old.len() {
+			if next_break.next_if(|&&b| b == i).is_some() {
+				while self.chars.last().is_some_and(|c| c.is_whitespace()) { // No whitespace on newlines
+					self.pop();
 				}
+				self.push('\n', Color::new_rgb(255, 255, 255), false, false);
 			}
+			self.push(old.chars[i], old.colors[i], old.underline[i], old.bold[i]);
+			self.set_background(self.len() - 1, old.backgrounds[i]);
 		}
-		
-		self.chars = new_chars;
-		self.colors = new_colors;
-		self.underline = new_underline;
-		self.bold = new_bold;
 	}
 }
 
+// Indices of the characters starting a new line when the text is wrapped to a max width
+// Words move to the next line whole, the whitespace before them is meant to be dropped
+// Words that can't fit on a line, or that start the text, are broken where they overflow
+pub fn wrap_indices(chars: &[char], width: usize) -> Vec<usize> {
+	let mut breaks = Vec::new();
+	let mut line_length = 0;
+	let mut word_start = 0; // Where the word being read starts, or its part on the current line
+	let mut first_word = true;
+	
+	for (i, &c) in chars.iter().enumerate() {
+		match c {
+			'\n' => {
+				word_start = i + 1;
+				first_word = false;
+				line_length = 0;
+			},
+			' ' => {
+				word_start = i + 1;
+				first_word = false;
+				line_length += 1;
+			},
+			_ if line_length + 1 > width => {
+				if first_word || i - word_start >= width {
+					breaks.push(i);
+					word_start = i;
+					first_word = false;
+					line_length = 1;
+				} else {
+					breaks.push(word_start);
+					line_length = i - word_start + 1;
+				}
+			},
+			_ => line_length += 1
+		}
+	}
+	breaks
+}
+
 impl fmt::Display for ColoredText {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		write!(f, "{}", &self.text())
 	}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn close(a: (f32, f32, f32), b: (f32, f32, f32)) -> bool {
+		(a.0 - b.0).abs() < 1.0 && (a.1 - b.1).abs() < 0.01 && (a.2 - b.2).abs() < 0.01
+	}
+	
+	#[test]
+	fn hsl() {
+		assert_eq!(Color::new_hsl(0.0, 1.0, 0.5), Color::new_rgb(255, 0, 0));
+		assert_eq!(Color::new_hsl(120.0, 1.0, 0.25), Color::new_rgb(0, 128, 0));
+		assert_eq!(Color::new_hsl(200.0, 0.0, 0.5), Color::new_rgb(128, 128, 128));
+		assert_eq!(Color::new_hsl(200.0, 0.7, 0.0), Color::new_rgb(0, 0, 0));
+		assert_eq!(Color::new_hsl(200.0, 0.7, 1.0), Color::new_rgb(255, 255, 255));
+		
+		assert!(close(Color::new_rgb(51, 102, 204).to_hsl(), (220.0, 0.6, 0.5)));
+		assert!(close(Color::new_rgb(128, 128, 128).to_hsl(), (0.0, 0.0, 0.5)));
+		assert!(close(Color::new_rgb(255, 255, 255).to_hsl(), (0.0, 0.0, 1.0)));
+		for color in [Color::new_rgb(255, 0, 128), Color::new_rgb(18, 200, 90), Color::new_rgb(40, 40, 41)] {
+			let (h, s, l) = color.to_hsl();
+			assert_eq!(Color::new_hsl(h, s, l), color);
+		}
+	}
+	
+	#[test]
+	fn hsv_round_trip() {
+		for (h, s, v) in [(0.0, 1.0, 1.0), (90.0, 0.5, 0.8), (210.0, 0.25, 0.4), (330.0, 1.0, 0.6)] {
+			assert!(close(Color::new_hsv(h, s, v).to_hsv(), (h, s, v)));
+		}
+		assert!(close(Color::new_rgb(0, 0, 0).to_hsv(), (0.0, 0.0, 0.0)));
+		assert!(close(Color::new_rgb(128, 128, 128).to_hsv(), (0.0, 0.0, 128.0 / 255.0)));
+	}
+	
+	#[test]
+	fn wrapping() {
+		let chars = |s: &str| s.chars().collect::<Vec<char>>();
+		assert_eq!(wrap_indices(&chars("the cat sat"), 7), vec![8]);
+		assert_eq!(wrap_indices(&chars("abc def"), 3), vec![4]); // Exact width lines
+		assert_eq!(wrap_indices(&chars("abcdefgh ij"), 3), vec![3, 6, 9]); // A long word is broken
+		assert_eq!(wrap_indices(&chars("ab cdefgh"), 3), vec![3, 6]);
+		assert_eq!(wrap_indices(&chars("ab\ncd"), 2), Vec::<usize>::new());
+		
+		let mut text = ColoredText::new_text("the cat sat", Color::new_rgb(0, 0, 0), false, false);
+		text.word_wrap(7);
+		assert_eq!(text.text(), "the cat\nsat");
+	}
+	
+	#[test]
+	fn long_first_word() {
+		// A word wider than the line as the very first one is broken instead of reading before the start
+		let mut text = ColoredText::new_text("abcdefghij kl", Color::new_rgb(0, 0, 0), false, false);
+		text.word_wrap(4);
+		assert_eq!(text.text(), "abcd\nefgh\nij\nkl");
+		let mut text = ColoredText::new_text("abcdefghij", Color::new_rgb(0, 0, 0), false, false);
+		text.word_wrap(1);
+		assert_eq!(text.text(), "a\nb\nc\nd\ne\nf\ng\nh\ni\nj");
+	}
+}
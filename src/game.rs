@@ -1 +1,1184 @@
-//TODO: something
\ No newline at end of file
+use std::{cmp::max, collections::VecDeque, ops::Range, time::{Duration, Instant}};
+
+use crossterm::{event::{self, KeyCode, KeyEvent, KeyEventKind, KeyModifiers}, terminal};
+use crossterm::event::KeyCode::Char as CharCode;
+
+use crate::color::*;
+use crate::keylog::KeyLog;
+use crate::pixels::*;
+use crate::theme::Theme;
+use crate::utils::nth_char_idx;
+
+const UNDO_LIMIT: usize = 100;
+const CONTEXT_LINES: usize = 1; // Lines above and below the caret's kept bright by --show-context
+const CONTEXT_DIM: f32 = 0.6; // How much --show-context fades the rest of the text into the background
+const SPACE_GLYPH: char = '·'; // Drawn on the spaces between words by --show-spaces
+const WORD_HIGHLIGHT: f32 = 0.15; // How much of the untyped color tints the background of the current word
+const SCROLL_EASE: f32 = 0.06; // Time constant of --smooth-scroll, in seconds
+const SCROLL_FRAME: Duration = Duration::from_millis(16); // Redraw interval of replays while the text scrolls
+const SCROLL_DIM: f32 = 0.6; // How much the text fades while it's between two rows
+const COLUMN_GAP: usize = 4; // Blank cells between two columns of --columns, and on each side of them
+pub const MINI_READOUT: usize = 8; // Columns left on the right of --mini for the speed
+
+// Typed text and cursor position before each space, restored by Ctrl+Z
+pub type UndoStack = VecDeque<(String, usize)>;
+
+// Options changing how the game behaves or looks
+#[derive(Default, Clone, Copy)]
+pub struct Settings {
+	pub single_line: bool, // Scroll the text horizontally on a single line instead of wrapping it
+	pub strict_space: bool, // Compare the text character by character, spaces included
+	pub bold: bool, // Draw all the text in bold
+	pub pace: Option<f32>, // Target speed of the ghost caret, in words per minute
+	pub fixed_caret: bool, // Keep the caret in place and scroll the text under it
+	pub border: bool, // Draw a box around the text
+	pub show_context: bool, // Dim the lines of wrapped text away from the caret
+	pub inverse_caret: bool, // Draw the carets in inverse video, without colors an underline alone is easy to miss
+	pub show_spaces: bool, // Draw the spaces left to type as faint dots
+	pub show_typed_spaces: bool, // Keep the dots on the spaces already typed
+	pub smooth_scroll: bool, // Ease the text toward its new row when --fixed-caret scrolls to another line
+	pub highlight_word: bool, // Give the word of the caret a faint background
+	pub hide_caret: bool, // Don't draw the caret, the colors of the typed text are enough to follow
+	pub mini: bool, // Draw the single line on the top row from the left edge, for panes a row or two high
+	pub lines: Option<usize>, // Wrapped lines shown around the caret's, all of them if None
+	pub skip_indent: bool, // Type the indentation of each line for the player, with strict_space
+	pub columns: usize, // Columns the wrapped lines flow through, one below 2
+}
+
+// Scrolling state of --smooth-scroll, kept between frames
+pub struct Scroll {
+	line: Option<usize>, // Line of the caret on the last frame
+	offset: f32, // Lines the text still has to move up, negative to move down
+	last: Instant,
+}
+
+impl Default for Scroll {
+	fn default() -> Scroll {
+		Scroll {
+			line: None,
+			offset: 0.0,
+			last: Instant::now(),
+		}
+	}
+}
+
+impl Scroll {
+	// Follow the caret to its line, the distance left shrinks exponentially with time
+	fn update(&mut self, line: usize) -> f32 {
+		let now = Instant::now();
+		self.offset *= (-(now - self.last).as_secs_f32() / SCROLL_EASE).exp();
+		if self.offset.abs() < 0.05 {
+			self.offset = 0.0;
+		}
+		if let Some(previous) = self.line {
+			self.offset += line as f32 - previous as f32;
+		}
+		self.line = Some(line);
+		self.last = now;
+		self.offset
+	}
+	
+	// Whether frames are needed to finish the movement
+	pub fn is_moving(&self) -> bool {
+		self.offset != 0.0
+	}
+}
+
+fn used_text_width(twidth: usize) -> usize {
+	let factor = match twidth {
+		0..50 => 0.8,
+		50..100 => 0.7,
+		100..170 => 0.6,
+		_ => 0.5
+	};
+	(twidth as f32 * factor) as usize
+}
+
+// Number of line breaks between each word of the text and the next one
+fn line_breaks(text: &str) -> Vec<usize> {
+	let mut breaks: Vec<usize> = Vec::new();
+	let mut in_word = false;
+	for c in text.trim_end().chars() {
+		if !c.is_whitespace() {
+			if !in_word {
+				breaks.push(0);
+			}
+			in_word = true;
+		} else {
+			if let (Some(b), '\n') = (breaks.last_mut(), c) {
+				*b += 1;
+			}
+			in_word = false;
+		}
+	}
+	breaks
+}
+
+// State of a character of the text shown to the player, along with the character to show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharStatus {
+	Correct(char), // Typed as in the text
+	Wrong(char), // Typed instead of the character of the text
+	Extra(char), // Typed past the end of a word
+	Missing(char), // Skipped in a word the player moved past
+	Untyped(char), // Not typed yet, or whitespace between words
+}
+
+// Compare the typed text to the text, word by word
+// Runs of spaces in the text are a single separator, typed with one space like any other
+pub fn char_statuses(typed: &str, text: &str) -> Vec<CharStatus> {
+	let mut statuses = Vec::new();
+	
+	let typed_words: Vec<&str> = typed.split_whitespace().collect();
+	let text_words: Vec<&str> = text.split_whitespace().collect();
+	let breaks = line_breaks(text);
+	
+	for i in 0..text_words.len() {
+		if i < typed_words.len() {
+			let text_chars: Vec<char> = text_words[i].chars().collect();
+			let typed_chars: Vec<char> = typed_words[i].chars().collect();
+			let passed = i + 1 < typed_words.len() || typed.ends_with(' ');
+			
+			for j in 0..text_chars.len() {
+				if *typed_chars.get(j).unwrap_or(&' ') == text_chars[j] {
+					statuses.push(CharStatus::Correct(typed_chars[j]));
+				} else if let Some(c) = typed_chars.get(j) {
+					statuses.push(CharStatus::Wrong(*c));
+				} else if passed {
+					statuses.push(CharStatus::Missing(text_chars[j]));
+				} else {
+					statuses.push(CharStatus::Untyped(text_chars[j]));
+				}
+			}
+			
+			// This is for when the player types longer than the word
+			for c in typed_chars.iter().skip(text_chars.len()) {
+				statuses.push(CharStatus::Extra(*c));
+			}
+			
+			// The space after a correct word is correct once the player moved on to the next word
+			if passed && typed_words[i] == text_words[i] {
+				statuses.push(CharStatus::Correct(' '));
+			} else {
+				statuses.push(CharStatus::Untyped(' '));
+			}
+		} else {
+			statuses.extend(text_words[i].chars().map(CharStatus::Untyped));
+			statuses.push(CharStatus::Untyped(' '));
+		}
+		for _ in 0..breaks[i] {
+			statuses.push(CharStatus::Untyped('\n'));
+		}
+	}
+	
+	statuses
+}
+
+// Same as char_statuses, but every character of the text has to be typed exactly, spaces included
+// Whitespace in the text, like line breaks, is typed as a space, and runs of spaces are typed one space at a time
+pub fn char_statuses_strict(typed: &str, text: &str) -> Vec<CharStatus> {
+	let mut statuses = Vec::new();
+	
+	let typed_chars: Vec<char> = typed.chars().collect();
+	let text_chars: Vec<char> = text.trim_end().chars().map(|c| if c.is_whitespace() { ' ' } else { c }).collect();
+	
+	for (i, &c) in text_chars.iter().enumerate() {
+		statuses.push(match typed_chars.get(i) {
+			Some(&t) if t == c => CharStatus::Correct(t),
+			Some(&t) => CharStatus::Wrong(t),
+			None => CharStatus::Untyped(c)
+		});
+	}
+	
+	// This is for when the player types past the end of the text
+	for c in typed_chars.iter().skip(text_chars.len()) {
+		statuses.push(CharStatus::Extra(*c));
+	}
+	statuses.push(CharStatus::Untyped(' ')); // Room for the caret at the end
+	
+	statuses
+}
+
+// Character ranges of the whitespace starting each line of the text, empty lines aside
+pub fn indent_ranges(text: &str) -> Vec<Range<usize>> {
+	let chars: Vec<char> = text.trim_end().chars().collect();
+	let mut ranges = Vec::new();
+	let mut line_start = 0;
+	for i in 0..=chars.len() {
+		if i == chars.len() || chars[i] == '\n' {
+			line_start = i + 1;
+		} else if !chars[i].is_whitespace() {
+			if i > line_start {
+				ranges.push(line_start..i);
+			}
+			line_start = usize::MAX;
+		}
+	}
+	ranges
+}
+
+// With --skip-indent, the typed text never stops inside the indentation of a line
+// Reaching it fills it with spaces, deleting into it removes it along with the line break before it
+// Only edits at the end of the typed text are handled, moving the cursor back leaves everything as is
+pub fn skip_indent(typed: &mut String, cursor_pos: &mut usize, text: &str, deleted: bool) {
+	let length = typed.chars().count();
+	if *cursor_pos != length {
+		return;
+	}
+	if let Some(range) = indent_ranges(text).into_iter().find(|r| r.contains(&length)) {
+		if deleted && range.start > 0 {
+			*typed = typed.chars().take(range.start - 1).collect();
+		} else {
+			typed.extend(std::iter::repeat_n(' ', range.end - length));
+		}
+		*cursor_pos = typed.chars().count();
+	}
+}
+
+// Draw the characters in the colors of the theme, mistakes are always bold
+// Mistyped spaces would be invisible, they are drawn as a middot instead
+fn colorize(statuses: &[CharStatus], theme: &Theme, bold: bool) -> ColoredText {
+	let visible = |c: char| if c == ' ' { '·' } else { c };
+	let mut out = ColoredText::new();
+	for status in statuses {
+		match *status {
+			CharStatus::Correct(c) => out.push(c, theme.typed, false, bold),
+			CharStatus::Wrong(c) => out.push(visible(c), theme.wrong, false, true),
+			CharStatus::Extra(c) => out.push(visible(c), theme.extra, false, true),
+			CharStatus::Missing(c) => out.push(c, theme.missing, false, bold),
+			CharStatus::Untyped(c) => out.push(c, theme.untyped, false, bold)
+		}
+	}
+	out
+}
+
+// Replace the spaces between words with a faint dot, spaces ending a line or the text stay blank
+// The spaces are found by their color, so the caret and wrong spaces keep their own look
+fn show_spaces(to_print: &mut ColoredText, theme: &Theme, settings: &Settings) {
+	let glyph_color = theme.untyped.blend(theme.background.unwrap_or(Color::new_rgb(0, 0, 0)), 0.5);
+	let chars = to_print.chars();
+	let colors = to_print.colors();
+	for i in 0..chars.len().saturating_sub(1) {
+		let shown = colors[i] == theme.untyped || (settings.show_typed_spaces && colors[i] == theme.typed);
+		if chars[i] == ' ' && chars[i + 1] != '\n' && shown {
+			to_print.set_char(i, SPACE_GLYPH);
+			to_print.set_color(i, glyph_color);
+		}
+	}
+}
+
+// Tint the background of the word the caret is in, or of the word it just finished
+fn highlight_word(to_print: &mut ColoredText, caret: usize, theme: &Theme) {
+	let chars = to_print.chars();
+	let is_gap = |c: char| c == ' ' || c == '\n';
+	let caret = caret.min(chars.len().saturating_sub(1));
+	let end = if chars.get(caret).is_some_and(|&c| is_gap(c)) {
+		caret
+	} else {
+		chars.iter().skip(caret).position(|&c| is_gap(c)).map_or(chars.len(), |p| caret + p)
+	};
+	let start = chars[..end].iter().rposition(|&c| is_gap(c)).map_or(0, |p| p + 1);
+	
+	let tint = theme.background.unwrap_or(Color::new_rgb(0, 0, 0)).blend(theme.untyped, WORD_HIGHLIGHT);
+	for i in start..end {
+		to_print.set_background(i, Some(tint));
+	}
+}
+
+// Underline the character under the cursor and return its index, which is still needed with a hidden caret
+fn show_cursor(to_print: &mut ColoredText, typed: &str, text: &str, cursor_pos: usize, color: Color, settings: &Settings) -> usize {
+	let typed_words: Vec<&str> = typed.split_whitespace().collect();
+	let text_words: Vec<&str> = text.split_whitespace().collect();
+	let breaks = line_breaks(text);
+	
+	let mut diff = 0;
+	
+	let space_terminated = typed.chars().last().unwrap_or('a').eq(&' ');
+	if !settings.strict_space && typed_words.len() > if space_terminated {0} else {1} {
+		for i in 0..(typed_words.len() - if space_terminated {0} else {1}) {
+			diff += max(text_words[i].len() as i32 - typed_words[i].len() as i32, 0) + breaks[i] as i32;
+		}
+	}
+	
+	let idx = (cursor_pos as i32 + diff) as usize;
+	if !settings.hide_caret {
+		to_print.set_underline(idx);
+		to_print.set_color(idx, color);
+	}
+	
+	idx
+}
+
+// Index in the displayed text of the character reached by a constant pace
+// A word counts as 5 characters, extra characters typed in a word push the ghost further
+fn pace_index(typed: &str, text: &str, wpm: f32, elapsed: Duration, settings: &Settings) -> usize {
+	let chars = (elapsed.as_secs_f32() / 60.0 * wpm * 5.0) as usize;
+	if settings.strict_space {
+		return chars.min(text.trim_end().chars().count());
+	}
+	
+	let typed_words: Vec<&str> = typed.split_whitespace().collect();
+	let text_words: Vec<&str> = text.split_whitespace().collect();
+	let breaks = line_breaks(text);
+	
+	let mut remaining = chars;
+	let mut idx = 0;
+	for (i, word) in text_words.iter().enumerate() {
+		let len = word.chars().count();
+		if remaining <= len {
+			return idx + remaining;
+		}
+		remaining -= len + 1;
+		idx += max(len, typed_words.get(i).map_or(0, |w| w.chars().count())) + 1 + breaks[i];
+	}
+	idx.saturating_sub(1)
+}
+
+// Number of typed characters matching the text, spaces after correct words included
+pub fn correct_chars(typed: &str, text: &str, settings: &Settings) -> usize {
+	if settings.strict_space {
+		correct_char_count(&char_statuses_strict(typed, text))
+	} else {
+		correct_char_count(&char_statuses(typed, text))
+	}
+}
+
+// Whether a wrong or extra character was typed anywhere, used by --death
+pub fn has_mistake(typed: &str, text: &str, settings: &Settings) -> bool {
+	let statuses = if settings.strict_space {
+		char_statuses_strict(typed, text)
+	} else {
+		char_statuses(typed, text)
+	};
+	statuses.iter().any(|s| matches!(s, CharStatus::Wrong(_) | CharStatus::Extra(_)))
+}
+
+// The reached words underlined, in the typed color if typed without a mistake and in the wrong color otherwise
+// Words are the runs of characters between whitespace, the ones the player didn't start are left out
+pub fn word_marks(typed: &str, text: &str, settings: &Settings, theme: &Theme) -> ColoredText {
+	let statuses = if settings.strict_space {
+		char_statuses_strict(typed, text)
+	} else {
+		char_statuses(typed, text)
+	};
+	let mut marks = ColoredText::new();
+	for word in statuses.split(|s| matches!(s, CharStatus::Correct(' ') | CharStatus::Untyped(' ') | CharStatus::Untyped('\n'))) {
+		if word.iter().all(|s| matches!(s, CharStatus::Untyped(_))) {
+			continue;
+		}
+		let perfect = word.iter().all(|s| matches!(s, CharStatus::Correct(_)));
+		if !marks.is_empty() {
+			marks.push(' ', theme.untyped, false, false);
+		}
+		for status in word {
+			let (CharStatus::Correct(c) | CharStatus::Wrong(c) | CharStatus::Extra(c) | CharStatus::Missing(c) | CharStatus::Untyped(c)) = *status;
+			marks.push(c, if perfect { theme.typed } else { theme.wrong }, true, !perfect);
+		}
+	}
+	marks
+}
+
+// Correct characters as counted for WPM, 5 of them making a word
+// The space after a word counts when the word is correct, as on monkeytype,
+// but the right letters of a wrong word count too where monkeytype drops the whole word,
+// so the WPM is a bit higher than monkeytype's after mistakes that were left in
+pub fn correct_char_count(statuses: &[CharStatus]) -> usize {
+	statuses.iter().filter(|s| matches!(s, CharStatus::Correct(_))).count()
+}
+
+// Faint ruler on the second row marking every 10th column of the text area, to debug the layout
+pub fn draw_ruler(pixels: &mut Pixels, theme: &Theme) {
+	let twidth = pixels.size.0;
+	let text_width = used_text_width(twidth);
+	let left = (twidth - text_width) / 2;
+	let color = theme.untyped.blend(theme.background.unwrap_or(Color::new_rgb(0, 0, 0)), 0.5);
+	
+	for x in 0..twidth {
+		let c = match x {
+			_ if x < left || x >= left + text_width => '-',
+			_ if (x - left).is_multiple_of(10) => '|',
+			_ => '.'
+		};
+		pixels.set_char((x, 1), c);
+		pixels.set_color((x, 1), color);
+	}
+	pixels.print(&format!("text {} / terminal {}", text_width, twidth), color, false, false, (twidth / 2, 2), HCentering::Center, VCentering::Top);
+}
+
+// Character of the text expected at the cursor, None past the end of a word
+pub fn expected_char(typed: &str, cursor_pos: usize, text: &str, settings: &Settings) -> Option<char> {
+	if settings.strict_space {
+		return text.trim_end().chars().nth(cursor_pos).map(|c| if c.is_whitespace() { ' ' } else { c });
+	}
+	
+	let before: String = typed.chars().take(cursor_pos).collect();
+	let word = before.split(' ').count() - 1;
+	let offset = before.split(' ').next_back().unwrap_or("").chars().count();
+	let text_word = text.split_whitespace().nth(word)?;
+	match text_word.chars().nth(offset) {
+		Some(c) => Some(c),
+		None if offset == text_word.chars().count() => Some(' '),
+		None => None
+	}
+}
+
+// Whether the word before the cursor matches the text, so a space can end it under --must-correct
+pub fn word_correct(typed: &str, cursor_pos: usize, text: &str, settings: &Settings) -> bool {
+	let before: Vec<char> = typed.chars().take(cursor_pos).collect();
+	let start = before.iter().rposition(|&c| c == ' ').map_or(0, |p| p + 1);
+	
+	if settings.strict_space {
+		let text_chars: Vec<char> = text.trim_end().chars().map(|c| if c.is_whitespace() { ' ' } else { c }).collect();
+		return text_chars.get(start..before.len()).is_some_and(|word| word == &before[start..])
+			&& text_chars.get(before.len()).is_none_or(|&c| c == ' ');
+	}
+	
+	// Without a word before it the space is ignored anyway
+	let word: String = before[start..].iter().collect();
+	if word.is_empty() {
+		return true;
+	}
+	let index = before[..start].iter().collect::<String>().split_whitespace().count();
+	text.split_whitespace().nth(index).is_some_and(|w| w == word)
+}
+
+// Whether the typed text reaches the end of the text
+// The last word ends the test once it's committed with a space or as long as in the text, mistakes or not
+// With strict_space the space has to come after at least a character of the last word
+pub fn is_finished(typed: &str, text: &str, settings: &Settings) -> bool {
+	if settings.strict_space {
+		let text_chars: Vec<char> = text.trim_end().chars().collect();
+		let last_word_start = text_chars.iter().rposition(|c| c.is_whitespace()).map_or(0, |i| i + 1);
+		return typed.chars().count() >= text_chars.len()
+			|| (typed.ends_with(' ') && typed.chars().skip(last_word_start).any(|c| !c.is_whitespace()));
+	}
+	
+	let typed_words: Vec<&str> = typed.split_whitespace().collect();
+	let text_words: Vec<&str> = text.split_whitespace().collect();
+	
+	typed_words.len() > text_words.len()
+	|| (typed_words.len() == text_words.len() && (typed.ends_with(' ')
+	|| typed_words.last().unwrap_or(&"").chars().count() >= text_words.last().unwrap_or(&"").chars().count()))
+}
+
+// Build the frame showing the typed text over the text to type
+// elapsed is the time since the first key press, used to place the ghost caret of --pace
+pub fn frame(typed: &str, text: &str, cursor_pos: usize, elapsed: Option<Duration>, theme: &Theme, settings: &Settings, scroll: &mut Scroll) -> Pixels {
+	frame_sized(terminal::size().unwrap(), typed, text, cursor_pos, elapsed, theme, settings, scroll)
+}
+
+// frame for a terminal of the given size
+#[allow(clippy::too_many_arguments)]
+fn frame_sized(tsize: (u16, u16), typed: &str, text: &str, cursor_pos: usize, elapsed: Option<Duration>, theme: &Theme, settings: &Settings, scroll: &mut Scroll) -> Pixels {
+	let mut pixels = Pixels::new((tsize.0 as usize, tsize.1 as usize));
+	pixels.background_all(theme.background);
+	
+	// Line breaks have no place on a single line
+	let flat_text;
+	let text = if settings.single_line && !settings.strict_space {
+		flat_text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+		&flat_text
+	} else {
+		text
+	};
+	
+	let statuses = if settings.strict_space {
+		char_statuses_strict(typed, text)
+	} else {
+		char_statuses(typed, text)
+	};
+	let mut to_print = colorize(&statuses, theme, settings.bold);
+	if let (Some(wpm), Some(elapsed)) = (settings.pace, elapsed) {
+		let ghost = pace_index(typed, text, wpm, elapsed, settings);
+		to_print.set_underline(ghost);
+		to_print.set_color(ghost, theme.pace);
+	}
+	let caret = show_cursor(&mut to_print, typed, text, cursor_pos, theme.caret, settings);
+	if settings.highlight_word {
+		highlight_word(&mut to_print, caret, theme);
+	}
+	
+	// Wide terminals can lay the wrapped lines out in columns, which share the whole width
+	let columns = if settings.single_line || settings.fixed_caret { 1 } else { settings.columns.max(1) };
+	let text_width = if settings.mini {
+		(tsize.0 as usize).saturating_sub(MINI_READOUT).max(1)
+	} else if columns > 1 {
+		((tsize.0 as usize).saturating_sub(COLUMN_GAP * (columns + 1)) / columns).max(1)
+	} else {
+		used_text_width(tsize.0 as usize)
+	};
+	let block_width = text_width * columns + COLUMN_GAP * (columns - 1); // Width of the columns and the gaps between them
+	
+	let middle = tsize.1 as usize / 2;
+	let mut position = (0, if settings.mini { 0 } else { middle });
+	let mut vcentering = if settings.mini { VCentering::Top } else { VCentering::Middle };
+	let mut caret_line = 0; // Line of the caret among the printed lines
+	let mut scroll_offset: f32 = 0.0; // Lines the text is drawn below its place by --smooth-scroll
+	
+	if settings.single_line && settings.fixed_caret {
+		// The caret stays in the middle of the screen and the text scrolls under it
+		let window_start = caret.saturating_sub(text_width / 2);
+		to_print = to_print.slice(window_start, caret + text_width - text_width / 2);
+		let area = if settings.mini { text_width } else { tsize.0 as usize };
+		position.0 = (area / 2).saturating_sub(caret - window_start);
+	} else if settings.single_line {
+		// Keep the caret in the middle of the window, unless it's close to either end of the text
+		let window_start = caret.saturating_sub(text_width / 2).min(to_print.len().saturating_sub(text_width));
+		to_print = to_print.slice(window_start, window_start + text_width);
+	} else {
+		// Line of the caret, wrapping up to the end of its word so it's split the same way
+		let caret_word_end = to_print.chars().iter().skip(caret).position(|&c| c == ' ').map_or(to_print.len(), |p| caret + p + 1);
+		let mut before_caret = to_print.slice(0, caret_word_end);
+		before_caret.word_wrap(text_width);
+		caret_line = before_caret.text().matches('\n').count();
+		
+		//TODO: Handle newlines
+		to_print.word_wrap(text_width);
+		// Rows of the viewport of --lines, never more than the terminal has
+		let lines_shown = settings.lines.map(|n| n.min(tsize.1 as usize).max(1));
+		
+		if settings.fixed_caret {
+			// The caret stays on the middle row and the lines scroll up under it
+			// With --smooth-scroll the line on the middle row lags behind the caret's for a few frames
+			if settings.smooth_scroll {
+				scroll_offset = scroll.update(caret_line);
+			}
+			let (hidden_lines, first_row) = fixed_caret_window(caret_line, scroll_offset, middle, lines_shown);
+			to_print = keep_lines(&to_print, hidden_lines, lines_shown.unwrap_or(usize::MAX));
+			position = ((tsize.0 as usize - text_width) / 2, first_row);
+			vcentering = VCentering::Top;
+			caret_line -= hidden_lines;
+		} else if let Some(n) = lines_shown {
+			// The caret's line stays in the middle of the viewport, unless it's close to either end of the text
+			let total = to_print.text().matches('\n').count() + 1;
+			let first = caret_line.saturating_sub((n - 1) / 2).min(total.saturating_sub(n));
+			to_print = keep_lines(&to_print, first, n);
+			caret_line -= first;
+		}
+	}
+	
+	if settings.show_spaces {
+		show_spaces(&mut to_print, theme, settings);
+	}
+	
+	if !settings.fixed_caret && !settings.mini {
+		position.0 = {
+			if to_print.text().matches('\n').count() > 0 {
+				(tsize.0 as usize).saturating_sub(block_width) / 2
+			} else {
+				// Center on the visible characters, not the trailing space left for the caret
+				(tsize.0 as usize).saturating_sub(to_print.text().trim_end().chars().count()) / 2
+			}
+		};
+	}
+	
+	let mut lines = to_print.text().matches('\n').count() + 1;
+	if columns > 1 {
+		// The columns are about as long as each other, and as long as the terminal at most, the next one taking over
+		lines = lines.div_ceil(columns).min(tsize.1 as usize).max(1);
+		caret_line %= lines;
+	}
+	let top = match vcentering {
+		VCentering::Middle => position.1 as i32 - lines as i32 / 2, // As print_color centers them
+		_ => position.1 as i32
+	};
+	
+	if settings.border {
+		// Wrapped or scrolling text gets a box as wide as the text area, so it doesn't change size while typing
+		let (left, width) = if settings.fixed_caret || lines > 1 {
+			((tsize.0 as i32 - block_width as i32) / 2, block_width)
+		} else {
+			(position.0 as i32, to_print.text().trim_end().chars().count())
+		};
+		pixels.draw_box((left - 3, top - 2), (width + 6, lines + 4), theme.untyped);
+	}
+	
+	if columns > 1 {
+		pixels.print_columns(&to_print, (position.0, top.max(0) as usize), text_width + COLUMN_GAP, lines);
+	} else {
+		pixels.print_color(
+			&to_print,
+			position,
+			HCentering::Left, // We don't center on the middle as that could cause some jitters
+			vcentering
+		);
+	}
+	if scroll_offset != 0.0 {
+		// A terminal can only move the text by whole rows, so it fades out before a step and back in after it
+		let between = 1.0 - (2.0 * scroll_offset.abs().fract() - 1.0).abs();
+		let mut shade = pixels.clone();
+		shade.color_all(theme.background.unwrap_or(Color::new_rgb(0, 0, 0)));
+		pixels.comp_blend(&shade, (tsize.0 as i32 / 2, tsize.1 as i32 / 2), between * SCROLL_DIM);
+	}
+	if settings.show_context && !settings.single_line {
+		// Dim everything, then put the lines around the caret back at full brightness
+		let caret_row = (top + caret_line as i32).max(0) as usize;
+		let first = caret_row.saturating_sub(CONTEXT_LINES);
+		let active = pixels.crop_rows(first, caret_row + CONTEXT_LINES + 1 - first);
+		let mut shade = pixels.clone();
+		shade.color_all(theme.background.unwrap_or(Color::new_rgb(0, 0, 0)));
+		pixels.comp_blend(&shade, (tsize.0 as i32 / 2, tsize.1 as i32 / 2), CONTEXT_DIM);
+		pixels.comp(&active, (active.size.0 as i32 / 2, (first + active.size.1 / 2) as i32));
+	}
+	if settings.inverse_caret {
+		pixels.inverse_underlined();
+	}
+	
+	pixels
+}
+
+// Lines hidden above the text of --fixed-caret, and the row of the first line left
+// The line drawn on the middle row lags behind the caret's by the scroll offset, but the caret's line is never hidden
+fn fixed_caret_window(caret_line: usize, scroll_offset: f32, middle: usize, lines_shown: Option<usize>) -> (usize, usize) {
+	let anchor = (caret_line as i32 - scroll_offset.round() as i32).max(0) as usize;
+	let above = lines_shown.map_or(middle, |n| (n - 1) / 2); // Rows of the viewport above the middle one
+	let hidden_lines = anchor.saturating_sub(above).min(caret_line);
+	(hidden_lines, middle - (anchor - hidden_lines).min(above))
+}
+
+// count lines of a wrapped text starting at line first, without the line return ending the last one
+fn keep_lines(text: &ColoredText, first: usize, count: usize) -> ColoredText {
+	let breaks: Vec<usize> = text.chars().iter().enumerate().filter(|(_, &c)| c == '\n').map(|(i, _)| i).collect();
+	let start = if first == 0 { 0 } else { breaks.get(first - 1).map_or(text.len(), |i| i + 1) };
+	let end = first.checked_add(count).and_then(|last| breaks.get(last - 1)).map_or(text.len(), |&i| i);
+	text.slice(start, end.max(start))
+}
+
+// Start of the word before the cursor, skipping the spaces right before it
+fn previous_word_start(typed: &str, cursor_pos: usize) -> usize {
+	let chars: Vec<char> = typed.chars().collect();
+	let mut pos = cursor_pos.min(chars.len());
+	while pos > 0 && chars[pos - 1] == ' ' {
+		pos -= 1;
+	}
+	while pos > 0 && chars[pos - 1] != ' ' {
+		pos -= 1;
+	}
+	pos
+}
+
+// End of the word after the cursor, skipping the spaces right after it
+fn next_word_end(typed: &str, cursor_pos: usize) -> usize {
+	let chars: Vec<char> = typed.chars().collect();
+	let mut pos = cursor_pos;
+	while pos < chars.len() && chars[pos] == ' ' {
+		pos += 1;
+	}
+	while pos < chars.len() && chars[pos] != ' ' {
+		pos += 1;
+	}
+	pos
+}
+
+// Apply a key press to the typed text and the cursor position
+pub fn edit(e: &KeyEvent, typed: &mut String, cursor_pos: &mut usize, undo: &mut UndoStack, settings: &Settings) {
+	if e.code == CharCode('z') && e.modifiers == KeyModifiers::CONTROL {
+		if let Some((t, c)) = undo.pop_back() {
+			*typed = t;
+			*cursor_pos = c;
+		}
+	} else if e.code == KeyCode::Backspace {
+		if *cursor_pos > 0 {
+			typed.remove(
+				nth_char_idx(typed, *cursor_pos - 1)
+			);
+			*cursor_pos -= 1;
+		}
+	} else if e.code == KeyCode::Delete {
+		if *cursor_pos < typed.chars().count() {
+			typed.remove(
+				nth_char_idx(typed, *cursor_pos)
+			);
+		}
+	} else if e.code == KeyCode::Left && e.modifiers.contains(KeyModifiers::CONTROL) {
+		*cursor_pos = previous_word_start(typed, *cursor_pos);
+	} else if e.code == KeyCode::Right && e.modifiers.contains(KeyModifiers::CONTROL) {
+		*cursor_pos = next_word_end(typed, *cursor_pos);
+	} else if e.code == KeyCode::Home {
+		*cursor_pos = 0;
+	} else if e.code == KeyCode::End {
+		*cursor_pos = typed.chars().count();
+	} else if e.code == KeyCode::Left {
+		*cursor_pos = cursor_pos.saturating_sub(1);
+	} else if e.code == KeyCode::Right {
+		if *cursor_pos < typed.chars().count() {
+			*cursor_pos += 1;
+		}
+	} else if let CharCode(c) = e.code {
+		if is_shortcut(e) {
+			return;
+		}
+		// Line breaks, from a paste for instance, are typed as spaces
+		let c = if c == '\n' || c == '\r' { ' ' } else { c };
+		let chars: Vec<char> = typed.chars().collect();
+		
+		// Spaces are only accepted between words, unless every space has to be typed
+		if c != ' '
+			|| settings.strict_space
+			|| (*cursor_pos >= chars.len() && *chars.last().unwrap_or(&' ') != ' ')
+			|| (*cursor_pos < chars.len() && *cursor_pos > 0 && chars[*cursor_pos - 1] != ' ' && chars[*cursor_pos] != ' ') {
+			
+			if c == ' ' {
+				undo.push_back((typed.clone(), *cursor_pos));
+				if undo.len() > UNDO_LIMIT {
+					undo.pop_front();
+				}
+			}
+			
+			if *cursor_pos >= chars.len() {
+				typed.push(c);
+			} else {
+				typed.insert(
+					nth_char_idx(typed, *cursor_pos),
+					c,
+				);
+			}
+			*cursor_pos += 1;
+		}
+	}
+}
+
+// Whether a character key is a shortcut rather than text, a raw newline also comes in as Ctrl+J
+// AltGr comes in as Ctrl+Alt on Windows, and types @ [ ] { } on many layouts
+pub fn is_shortcut(e: &KeyEvent) -> bool {
+	e.modifiers.contains(KeyModifiers::CONTROL) && !e.modifiers.contains(KeyModifiers::ALT)
+}
+
+// Whether a key event is a press or a held key repeating
+// Some terminals, and Windows, also report releases, which would type every character twice
+pub fn is_press(e: &KeyEvent) -> bool {
+	matches!(e.kind, KeyEventKind::Press | KeyEventKind::Repeat)
+}
+
+// The key pressed by an event, None for releases and the events that aren't keys
+pub fn key_press(event: event::Event) -> Option<KeyEvent> {
+	match event {
+		event::Event::Key(e) if is_press(&e) => Some(e),
+		_ => None
+	}
+}
+
+// Key presses typing out pasted text
+pub fn paste_keys(text: &str) -> Vec<KeyEvent> {
+	text.chars().map(|c| KeyEvent::new(CharCode(c), KeyModifiers::NONE)).collect()
+}
+
+// Apply a recorded key press, along with the indentation --skip-indent types after it
+fn replay_key(key: &KeyEvent, typed: &mut String, cursor_pos: &mut usize, undo: &mut UndoStack, text: &str, settings: &Settings) {
+	let length = typed.chars().count();
+	edit(key, typed, cursor_pos, undo, settings);
+	if settings.skip_indent {
+		let deleted = typed.chars().count() < length;
+		skip_indent(typed, cursor_pos, text, deleted);
+	}
+}
+
+// Play back a recorded run at its original speed
+// Space pauses and resumes, right arrow steps through keys while paused, q or Ctrl+C quits
+pub fn replay(log: &KeyLog, theme: &Theme, settings: &Settings, term_color_support: &TermColorSupport) {
+	let mut typed = String::new();
+	let mut cursor_pos = 0;
+	let mut undo = UndoStack::new();
+	if settings.skip_indent {
+		skip_indent(&mut typed, &mut cursor_pos, &log.text, false);
+	}
+	
+	let mut next = 0; // Index of the next event to play
+	let mut elapsed = Duration::ZERO;
+	let mut last_tick = Instant::now();
+	let mut paused = false;
+	let mut scroll = Scroll::default();
+	
+	loop {
+		let since_first_key = log.events.first().filter(|_| next > 0).map(|e| elapsed.saturating_sub(e.time));
+		let mut pixels = frame(&typed, &log.text, cursor_pos, since_first_key, theme, settings, &mut scroll);
+		let status = format!(
+			"replay {}/{}{}  [space] pause  [→] step  [q] quit",
+			next,
+			log.events.len(),
+			if paused { " (paused)" } else { "" }
+		);
+		pixels.print(&status, theme.untyped, false, false, (pixels.size.0 / 2, pixels.size.1 - 1), HCentering::Center, VCentering::Bottom);
+		pixels.render(term_color_support);
+		
+		// Wait for user input until the next event is due
+		let timeout = if paused || next >= log.events.len() {
+			None
+		} else {
+			Some(log.events[next].time.saturating_sub(elapsed))
+		};
+		// Keep drawing while the text scrolls
+		let timeout = match (timeout, scroll.is_moving()) {
+			(Some(t), true) => Some(t.min(SCROLL_FRAME)),
+			(None, true) => Some(SCROLL_FRAME),
+			(timeout, false) => timeout
+		};
+		let available = match timeout {
+			Some(t) => event::poll(t).unwrap(),
+			None => true
+		};
+		if available {
+			if let Some(e) = key_press(event::read().unwrap()) {
+				if e.code == CharCode('q') || (e.code == CharCode('c') && e.modifiers == event::KeyModifiers::CONTROL) {
+					break;
+				} else if e.code == CharCode(' ') {
+					paused = !paused;
+				} else if e.code == KeyCode::Right && paused && next < log.events.len() {
+					replay_key(&log.events[next].key, &mut typed, &mut cursor_pos, &mut undo, &log.text, settings);
+					elapsed = log.events[next].time;
+					next += 1;
+				}
+			}
+		}
+		
+		if !paused {
+			elapsed += last_tick.elapsed();
+		}
+		last_tick = Instant::now();
+		
+		while !paused && next < log.events.len() && log.events[next].time <= elapsed {
+			replay_key(&log.events[next].key, &mut typed, &mut cursor_pos, &mut undo, &log.text, settings);
+			next += 1;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use CharStatus::*;
+	
+	fn statuses(typed: &str, text: &str) -> Vec<CharStatus> {
+		char_statuses(typed, text)
+	}
+	
+	fn combine(typed: &str, text: &str) -> (ColoredText, Vec<CharStatus>) {
+		let statuses = char_statuses(typed, text);
+		(colorize(&statuses, &Theme::default(), false), statuses)
+	}
+	
+	#[test]
+	fn partial_word() {
+		assert_eq!(statuses("th", "the cat"), vec![
+			Correct('t'), Correct('h'), Untyped('e'), Untyped(' '),
+			Untyped('c'), Untyped('a'), Untyped('t'), Untyped(' '),
+		]);
+	}
+	
+	#[test]
+	fn wrong_char() {
+		let (colored, statuses) = combine("tbe", "the");
+		assert_eq!(statuses, vec![Correct('t'), Wrong('b'), Correct('e'), Untyped(' ')]);
+		assert_eq!(colored.text(), "tbe ");
+		assert_eq!(colored.colors()[1], Theme::default().wrong);
+		assert_eq!(colored.bold(), vec![false, true, false, false]);
+	}
+	
+	#[test]
+	fn overtyped_word() {
+		let (colored, statuses) = combine("thee cat", "the cat");
+		assert_eq!(&statuses[..5], &[Correct('t'), Correct('h'), Correct('e'), Extra('e'), Untyped(' ')]);
+		assert_eq!(colored.text(), "thee cat ");
+		assert_eq!(colored.colors()[3], Theme::default().extra);
+	}
+	
+	#[test]
+	fn skipped_chars() {
+		assert_eq!(statuses("th ", "the cat"), vec![
+			Correct('t'), Correct('h'), Missing('e'), Untyped(' '),
+			Untyped('c'), Untyped('a'), Untyped('t'), Untyped(' '),
+		]);
+	}
+	
+	#[test]
+	fn fully_correct() {
+		let (colored, statuses) = combine("the cat", "the cat");
+		assert!(statuses.iter().all(|s| matches!(s, Correct(_) | Untyped(' '))));
+		assert!(colored.colors().iter().zip(&statuses).all(|(c, s)| matches!(s, Untyped(_)) || *c == Theme::default().typed));
+	}
+	
+	#[test]
+	fn typed_beyond_text() {
+		assert_eq!(statuses("cats", "cat"), vec![Correct('c'), Correct('a'), Correct('t'), Extra('s'), Untyped(' ')]);
+	}
+	
+	#[test]
+	fn strict_spaces() {
+		assert_eq!(char_statuses_strict("a  b", "a b\nc"), vec![
+			Correct('a'), Correct(' '), Wrong(' '), Wrong('b'), Untyped('c'), Untyped(' '),
+		]);
+	}
+	
+	#[test]
+	fn paste_newline() {
+		let (mut typed, mut cursor_pos, mut undo) = (String::new(), 0, UndoStack::new());
+		for key in paste_keys("foo\nbar") {
+			edit(&key, &mut typed, &mut cursor_pos, &mut undo, &Settings::default());
+		}
+		assert_eq!(typed, "foo bar");
+		assert_eq!(cursor_pos, 7);
+	}
+	
+	#[test]
+	fn expected_chars() {
+		let settings = Settings::default();
+		assert_eq!(expected_char("th", 2, "the cat", &settings), Some('e'));
+		assert_eq!(expected_char("the", 3, "the cat", &settings), Some(' '));
+		assert_eq!(expected_char("the c", 5, "the cat", &settings), Some('a'));
+		assert_eq!(expected_char("thee", 4, "the cat", &settings), None);
+	}
+	
+	#[test]
+	fn inverse_caret_without_colors() {
+		let mut to_print = colorize(&statuses("t", "the"), &Theme::default(), false);
+		let idx = show_cursor(&mut to_print, "t", "the", 1, Theme::default().caret, &Settings::default());
+		let mut pixels = Pixels::new((10, 1));
+		pixels.print_color(&to_print, (0, 0), HCentering::Left, VCentering::Top);
+		pixels.inverse_underlined();
+		
+		let ansi = pixels.to_ansi(&TermColorSupport::None);
+		let caret = ansi.find(to_print.chars()[idx]).unwrap();
+		assert!(ansi[..caret].ends_with("\x1b[4;7m"));
+	}
+	
+	#[test]
+	fn word_jumps() {
+		assert_eq!(previous_word_start("the cat sat", 9), 8);
+		assert_eq!(previous_word_start("the cat sat", 8), 4);
+		assert_eq!(previous_word_start("the cat sat", 2), 0);
+		assert_eq!(next_word_end("the cat sat", 1), 3);
+		assert_eq!(next_word_end("the cat sat", 3), 7);
+		assert_eq!(next_word_end("the cat sat", 11), 11);
+	}
+	
+	#[test]
+	fn home_end() {
+		let (mut typed, mut cursor_pos, mut undo) = ("the ca".to_string(), 3, UndoStack::new());
+		edit(&KeyEvent::from(KeyCode::Home), &mut typed, &mut cursor_pos, &mut undo, &Settings::default());
+		assert_eq!(cursor_pos, 0);
+		edit(&KeyEvent::from(KeyCode::End), &mut typed, &mut cursor_pos, &mut undo, &Settings::default());
+		assert_eq!(cursor_pos, 6);
+	}
+	
+	#[test]
+	fn finish_on_last_word() {
+		let settings = Settings::default();
+		assert!(!is_finished("the ca", "the cat", &settings));
+		assert!(is_finished("the cat", "the cat", &settings));
+		assert!(is_finished("the cax", "the cat", &settings));
+		assert!(is_finished("the catt", "the cat", &settings));
+		assert!(is_finished("the ca ", "the cat", &settings));
+		assert!(is_finished("the c ", "the cat", &settings));
+		assert!(is_finished("the cat sat", "the cat", &settings));
+	}
+	
+	#[test]
+	fn strict_finish_on_last_word() {
+		let settings = Settings { strict_space: true, ..Settings::default() };
+		assert!(!is_finished("the ca", "the cat", &settings));
+		assert!(is_finished("the cat", "the cat", &settings));
+		assert!(is_finished("the cax", "the cat", &settings));
+		assert!(is_finished("the c ", "the cat", &settings)); // A space after the last word, however short
+		assert!(is_finished("the  c ", "the cat", &settings));
+		assert!(!is_finished("the ", "the cat", &settings)); // The space before it doesn't count
+		assert!(!is_finished("th ", "the cat", &settings));
+	}
+	
+	#[test]
+	fn correct_char_definition() {
+		let count = |typed: &str| correct_char_count(&statuses(typed, "the cat sat"));
+		assert_eq!(count("the"), 3);
+		assert_eq!(count("the "), 4);
+		assert_eq!(count("the c"), 5);
+		// Unlike monkeytype, t and h of the wrong word count, its space doesn't
+		assert_eq!(count("thx cat "), 6);
+		assert_eq!(count("the cat sat"), 11);
+		assert_eq!(correct_char_count(&char_statuses_strict("the cxt", "the cat")), 6);
+	}
+	
+	#[test]
+	fn spaces_shown_as_dots() {
+		let theme = Theme::default();
+		let settings = Settings { show_spaces: true, ..Settings::default() };
+		let mut to_print = colorize(&statuses("the ", "the cat sat"), &theme, false);
+		show_spaces(&mut to_print, &theme, &settings);
+		assert_eq!(to_print.text(), "the cat·sat ");
+		
+		let settings = Settings { show_typed_spaces: true, ..settings };
+		let mut to_print = colorize(&statuses("the ", "the cat sat"), &theme, false);
+		show_spaces(&mut to_print, &theme, &settings);
+		assert_eq!(to_print.text(), "the·cat·sat ");
+	}
+	
+	#[test]
+	fn mistakes() {
+		let settings = Settings::default();
+		assert!(!has_mistake("the c", "the cat", &settings));
+		assert!(has_mistake("the x", "the cat", &settings));
+		assert!(has_mistake("thee", "the cat", &settings));
+		assert!(!has_mistake("", "the cat", &settings));
+	}
+	
+	#[test]
+	fn scroll_eases_to_new_line() {
+		let mut scroll = Scroll::default();
+		assert_eq!(scroll.update(2), 0.0);
+		let offset = scroll.update(3);
+		assert!(offset > 0.5 && offset <= 1.0);
+		assert!(scroll.is_moving());
+		scroll.last -= Duration::from_secs(1);
+		assert_eq!(scroll.update(3), 0.0);
+		assert!(!scroll.is_moving());
+	}
+	
+	#[test]
+	fn double_spaces() {
+		let settings = Settings::default();
+		assert_eq!(char_statuses("a b", "a  b"), vec![Correct('a'), Correct(' '), Correct('b'), Untyped(' ')]);
+		assert!(is_finished("a b", "a  b", &settings));
+		
+		let settings = Settings { strict_space: true, ..Settings::default() };
+		assert_eq!(char_statuses_strict("a b", "a  b"), vec![Correct('a'), Correct(' '), Wrong('b'), Untyped('b'), Untyped(' ')]);
+		assert!(!is_finished("a b", "a  b", &settings));
+		assert!(is_finished("a  b", "a  b", &settings));
+	}
+	
+	#[test]
+	fn highlighted_word() {
+		let theme = Theme::default();
+		let highlighted = |typed: &str, caret: usize| {
+			let mut to_print = colorize(&statuses(typed, "the cat sat"), &theme, false);
+			highlight_word(&mut to_print, caret, &theme);
+			to_print.backgrounds().iter().map(|b| if b.is_some() { '#' } else { '.' }).collect::<String>()
+		};
+		assert_eq!(highlighted("the c", 5), "....###.....");
+		assert_eq!(highlighted("the", 3), "###.........");
+		assert_eq!(highlighted("", 0), "###.........");
+	}
+	
+	#[test]
+	fn word_must_be_correct() {
+		let settings = Settings::default();
+		assert!(!word_correct("the ca", 6, "the cat", &settings));
+		assert!(word_correct("the cat", 7, "the cat", &settings));
+		assert!(!word_correct("the cax", 7, "the cat", &settings));
+		assert!(!word_correct("the catt", 8, "the cat", &settings));
+		assert!(!word_correct("thx", 3, "the cat", &settings));
+		assert!(word_correct("the ", 4, "the cat", &settings));
+		
+		let settings = Settings { strict_space: true, ..Settings::default() };
+		assert!(word_correct("the", 3, "the cat", &settings));
+		assert!(!word_correct("th", 2, "the cat", &settings));
+		assert!(!word_correct("tha", 3, "the cat", &settings));
+	}
+	
+	#[test]
+	fn marked_words() {
+		let theme = Theme::default();
+		let marks = word_marks("the cst ", "the cat sat", &Settings::default(), &theme);
+		assert_eq!(marks.text(), "the cst");
+		assert_eq!(marks.colors()[0], theme.typed);
+		assert_eq!(marks.colors()[4], theme.wrong);
+		assert!(marks.underline()[0] && !marks.underline()[3]);
+		assert_eq!(word_marks("", "the cat", &Settings::default(), &theme).text(), "");
+	}
+	
+	#[test]
+	fn hidden_caret() {
+		let settings = Settings { hide_caret: true, ..Settings::default() };
+		let mut to_print = colorize(&statuses("t", "the"), &Theme::default(), false);
+		let idx = show_cursor(&mut to_print, "t", "the", 1, Theme::default().caret, &settings);
+		assert_eq!(idx, 1);
+		assert!(!to_print.underline()[idx]);
+		assert_eq!(to_print.colors()[idx], Theme::default().untyped);
+	}
+	
+	#[test]
+	fn viewport_lines() {
+		let text = ColoredText::new_text("one\ntwo\nthree\nfour", Color::new_rgb(0, 0, 0), false, false);
+		assert_eq!(keep_lines(&text, 1, 2).text(), "two\nthree");
+		assert_eq!(keep_lines(&text, 0, 1).text(), "one");
+		assert_eq!(keep_lines(&text, 2, 5).text(), "three\nfour");
+		assert_eq!(keep_lines(&text, 0, usize::MAX).text(), "one\ntwo\nthree\nfour");
+		assert_eq!(keep_lines(&text, 6, 1).text(), "");
+	}
+	
+	#[test]
+	fn indentation() {
+		let text = "fn main() {\n\tlet a;\n\n    }\n";
+		assert_eq!(indent_ranges(text), vec![12..13, 21..25]);
+		assert_eq!(indent_ranges("  a"), vec![0..2]);
+		
+		let (mut typed, mut cursor_pos) = ("fn main() { ".to_string(), 12);
+		skip_indent(&mut typed, &mut cursor_pos, text, false);
+		assert_eq!((typed.as_str(), cursor_pos), ("fn main() {  ", 13));
+		// Backspacing out of the line's first character takes the indentation and the line break
+		let (mut typed, mut cursor_pos) = ("fn main() {  ".to_string(), 13);
+		typed.pop();
+		cursor_pos -= 1;
+		skip_indent(&mut typed, &mut cursor_pos, text, true);
+		assert_eq!((typed.as_str(), cursor_pos), ("fn main() {", 11));
+		
+		let (mut typed, mut cursor_pos) = (String::new(), 0);
+		skip_indent(&mut typed, &mut cursor_pos, "  a", false);
+		assert_eq!(typed, "  ");
+	}
+	
+	#[test]
+	fn releases_ignored() {
+		let press = KeyEvent::from(CharCode('a'));
+		let release = KeyEvent { kind: KeyEventKind::Release, ..press };
+		let repeat = KeyEvent { kind: KeyEventKind::Repeat, ..press };
+		assert_eq!(key_press(event::Event::Key(press)), Some(press));
+		assert_eq!(key_press(event::Event::Key(release)), None);
+		assert_eq!(key_press(event::Event::Key(repeat)), Some(repeat));
+		assert_eq!(key_press(event::Event::FocusLost), None);
+	}
+	
+	#[test]
+	fn scroll_back_keeps_caret_shown() {
+		let mut scroll = Scroll::default();
+		scroll.update(6);
+		// Backspacing onto the line above moves the text down, the anchor is below the caret for a few frames
+		let offset = scroll.update(5);
+		assert!(offset < 0.0);
+		assert_eq!(fixed_caret_window(5, offset, 10, Some(1)), (5, 10));
+		assert_eq!(fixed_caret_window(5, offset, 10, Some(2)), (5, 10));
+		assert_eq!(fixed_caret_window(0, -3.0, 10, Some(3)), (0, 9));
+		assert_eq!(fixed_caret_window(0, -3.0, 10, None), (0, 7));
+		assert_eq!(fixed_caret_window(12, 0.0, 10, None), (2, 0));
+	}
+	
+	#[test]
+	fn replayed_indentation() {
+		let settings = Settings { strict_space: true, skip_indent: true, ..Settings::default() };
+		let text = "a\n\tb";
+		let (mut typed, mut cursor_pos, mut undo) = (String::new(), 0, UndoStack::new());
+		for c in ['a', ' ', 'b'] {
+			replay_key(&KeyEvent::from(CharCode(c)), &mut typed, &mut cursor_pos, &mut undo, text, &settings);
+		}
+		assert_eq!((typed.as_str(), cursor_pos), ("a  b", 4));
+		replay_key(&KeyEvent::from(KeyCode::Backspace), &mut typed, &mut cursor_pos, &mut undo, text, &settings);
+		assert_eq!((typed.as_str(), cursor_pos), ("a  ", 3));
+		replay_key(&KeyEvent::from(KeyCode::Backspace), &mut typed, &mut cursor_pos, &mut undo, text, &settings);
+		assert_eq!((typed.as_str(), cursor_pos), ("a", 1));
+	}
+	
+	#[test]
+	fn altgr_characters() {
+		let settings = Settings::default();
+		let (mut typed, mut cursor_pos, mut undo) = (String::new(), 0, UndoStack::new());
+		edit(&KeyEvent::new(CharCode('@'), KeyModifiers::CONTROL | KeyModifiers::ALT), &mut typed, &mut cursor_pos, &mut undo, &settings);
+		edit(&KeyEvent::new(CharCode('j'), KeyModifiers::CONTROL), &mut typed, &mut cursor_pos, &mut undo, &settings);
+		edit(&KeyEvent::new(CharCode('{'), KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT), &mut typed, &mut cursor_pos, &mut undo, &settings);
+		assert_eq!((typed.as_str(), cursor_pos), ("@{", 2));
+	}
+	
+	#[test]
+	fn short_text_centered() {
+		let pixels = frame_sized((20, 3), "", "the cat ", 0, None, &Theme::default(), &Settings::default(), &mut Scroll::default());
+		let middle: String = (0..20).map(|x| pixels.get_pixel((x, 1)).unwrap().0).collect();
+		// 6 cells on the left and 7 on the right, the trailing space doesn't count
+		assert_eq!(middle, "      the cat       ");
+	}
+}
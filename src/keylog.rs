@@ -0,0 +1,138 @@
+use std::{fs::{self, File, OpenOptions}, io::Write, time::Duration};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+pub struct KeyLogEvent {
+	pub time: Duration, // Time since the start of the run
+	pub key: KeyEvent,
+}
+
+// Every key press of a run, used to replay it later
+pub struct KeyLog {
+	pub text: String,
+	pub events: Vec<KeyLogEvent>,
+	pub strict_space: bool, // Settings changing what the keys do, so the replay types the same text
+	pub skip_indent: bool,
+}
+
+// Name of a key as stored in key logs
+pub fn key_name(code: KeyCode) -> Option<String> {
+	match code {
+		KeyCode::Char(c) => Some(c.to_string()),
+		KeyCode::Backspace => Some("Backspace".to_string()),
+		KeyCode::Delete => Some("Delete".to_string()),
+		KeyCode::Left => Some("Left".to_string()),
+		KeyCode::Right => Some("Right".to_string()),
+		KeyCode::Home => Some("Home".to_string()),
+		KeyCode::End => Some("End".to_string()),
+		_ => None
+	}
+}
+
+// Key from its name in a key log
+fn key_code(name: &str) -> Option<KeyCode> {
+	let mut chars = name.chars();
+	match (chars.next(), chars.next()) {
+		(Some(c), None) => Some(KeyCode::Char(c)),
+		_ => match name {
+			"Backspace" => Some(KeyCode::Backspace),
+			"Delete" => Some(KeyCode::Delete),
+			"Left" => Some(KeyCode::Left),
+			"Right" => Some(KeyCode::Right),
+			"Home" => Some(KeyCode::Home),
+			"End" => Some(KeyCode::End),
+			_ => None
+		}
+	}
+}
+
+impl KeyLog {
+	pub fn new(text: &str, strict_space: bool, skip_indent: bool) -> KeyLog {
+		KeyLog {
+			text: text.to_string(),
+			events: Vec::new(),
+			strict_space,
+			skip_indent,
+		}
+	}
+	
+	// Record a key press, keys that can't be replayed are ignored
+	pub fn push(&mut self, time: Duration, key: KeyEvent) {
+		if key_name(key.code).is_some() {
+			self.events.push(KeyLogEvent { time, key });
+		}
+	}
+	
+	// Load a key log from a JSON file
+	pub fn load(path: &str) -> KeyLog {
+		let contents = fs::read_to_string(path).unwrap_or_else(|_| panic!("Can't open {}. Does the file exist ?", path));
+		let parsed = jzon::parse(&contents).unwrap_or_else(|_| panic!("{} is not a valid key log.", path));
+		
+		// Logs from before the settings were stored were recorded without them
+		let mut log = KeyLog::new(
+			parsed["text"].as_str().expect("The key log has no text."),
+			parsed["strict_space"].as_bool().unwrap_or(false),
+			parsed["skip_indent"].as_bool().unwrap_or(false)
+		);
+		for event in parsed["events"].members() {
+			let code = event["key"].as_str().and_then(key_code).expect("The key log contains an unknown key.");
+			let modifiers = KeyModifiers::from_bits_truncate(event["mods"].as_u8().unwrap_or(0));
+			log.events.push(KeyLogEvent {
+				time: Duration::from_millis(event["t"].as_u64().expect("The key log contains an event without a time.")),
+				key: KeyEvent::new(code, modifiers),
+			});
+		}
+		
+		log
+	}
+	
+	// Write the key log to a JSON file
+	pub fn save(&self, path: &str) {
+		let mut events = jzon::JsonValue::new_array();
+		for event in &self.events {
+			events.push(jzon::object!{
+				t: event.time.as_millis() as u64,
+				key: key_name(event.key.code),
+				mods: event.key.modifiers.bits(),
+			}).unwrap();
+		}
+		
+		let log = jzon::object!{
+			text: self.text.as_str(),
+			strict_space: self.strict_space,
+			skip_indent: self.skip_indent,
+			events: events,
+		};
+		fs::write(path, log.dump()).unwrap_or_else(|_| panic!("Can't write the key log to {}.", path));
+	}
+}
+
+// Key presses written as JSON lines while the test goes on, for overlays and other live tools
+pub struct EventStream {
+	file: File,
+}
+
+impl EventStream {
+	// Open a file to stream to, a number being a file descriptor already open in the shell (e.g. 3 for 3>file)
+	pub fn open(target: &str) -> EventStream {
+		let path = match target.parse::<u32>() {
+			Ok(fd) => format!("/dev/fd/{}", fd),
+			Err(_) => target.to_string()
+		};
+		let file = OpenOptions::new().create(true).append(true).open(&path).unwrap_or_else(|_| panic!("Can't open {} to stream the key presses.", path));
+		EventStream { file }
+	}
+	
+	// Write one key press, typed being the character it added to the text if any, and expected the one the text wanted there
+	pub fn send(&mut self, time: Duration, key: KeyEvent, expected: Option<char>, typed: Option<char>) {
+		let event = jzon::object!{
+			t: time.as_millis() as u64,
+			key: key_name(key.code),
+			expected: typed.and(expected).map(|c| c.to_string()),
+			correct: typed.map(|c| Some(c) == expected),
+		};
+		// A reader going away shouldn't end the test, so write errors are ignored
+		let _ = writeln!(self.file, "{}", event.dump());
+		let _ = self.file.flush();
+	}
+}
@@ -0,0 +1,59 @@
+use std::{collections::HashMap, fs};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+// Keys of a QWERTY keyboard, the built-in layouts give the character of each of these keys
+const QWERTY: &str = "qwertyuiop[]asdfghjkl;'zxcvbnm,./-=QWERTYUIOP{}ASDFGHJKL:\"ZXCVBNM<>?_+";
+const DVORAK: &str = "',.pyfgcrl/=aoeuidhtns-;qjkxbmwvz[]\"<>PYFGCRL?+AOEUIDHTNS_:QJKXBMWVZ{}";
+const COLEMAK: &str = "qwfpgjluy;[]arstdhneio'zxcvbkm,./-=QWFPGJLUY:{}ARSTDHNEIO\"ZXCVBKM<>?_+";
+
+// Keyboard layout to practice while the system still types in QWERTY
+pub struct Layout {
+	map: HashMap<char, char>,
+}
+
+impl Layout {
+	fn from_keys(keys: &str) -> Layout {
+		Layout {
+			map: QWERTY.chars().zip(keys.chars()).collect(),
+		}
+	}
+	
+	// Get one of the built-in layouts by name
+	pub fn from_name(name: &str) -> Option<Layout> {
+		match name {
+			"dvorak" => Some(Layout::from_keys(DVORAK)),
+			"colemak" => Some(Layout::from_keys(COLEMAK)),
+			_ => None
+		}
+	}
+	
+	// Load a layout from a JSON object giving the character typed by each QWERTY key, e.g. {"q": "'"}
+	pub fn load(path: &str) -> Layout {
+		let contents = fs::read_to_string(path).unwrap_or_else(|_| panic!("There is no layout called {} and no such file.", path));
+		let parsed = jzon::parse(&contents).unwrap_or_else(|_| panic!("{} is not a valid layout.", path));
+		
+		let mut map = HashMap::new();
+		for (key, value) in parsed.entries() {
+			let mut key_chars = key.chars();
+			let mut value_chars = value.as_str().unwrap_or("").chars();
+			match (key_chars.next(), key_chars.next(), value_chars.next(), value_chars.next()) {
+				(Some(k), None, Some(v), None) => { map.insert(k, v); },
+				_ => panic!("{} must map single characters to single characters.", path)
+			}
+		}
+		
+		Layout { map }
+	}
+	
+	// Translate a key press to the character the layout has on that key
+	// Shortcuts keep their QWERTY keys
+	pub fn remap(&self, key: KeyEvent) -> KeyEvent {
+		match key.code {
+			KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+				KeyEvent { code: KeyCode::Char(*self.map.get(&c).unwrap_or(&c)), ..key }
+			},
+			_ => key
+		}
+	}
+}
@@ -0,0 +1,16 @@
+// Everything the ttype binary is made of, score can also be used on its own to grade a typed text
+
+pub mod color;
+pub mod pixels;
+pub mod term_colors;
+pub mod utils;
+pub mod game;
+pub mod keylog;
+pub mod theme;
+pub mod menu;
+pub mod stats;
+pub mod layout;
+pub mod results;
+pub mod tui;
+
+pub use stats::{score, RunResult};
@@ -1,26 +1,56 @@
-use std::{env, fs::File, io::{stdout, Read}, panic, str::FromStr, time::Duration, cmp::max};
+use std::{collections::{HashMap, HashSet}, env, fmt, fs::{self, File}, io::{stdout, Read}, panic, thread, time::{Duration, Instant, SystemTime}};
 
 use getopts::Options;
-use crossterm::{cursor, event::{self, KeyCode}, execute, terminal};
+use crossterm::{event, execute, terminal};
 use crossterm::event::KeyCode::Char as CharCode;
-use rand::Rng;
+use rand::{distr::{weighted::WeightedIndex, Distribution}, seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
-mod color;
-mod pixels;
-mod term_colors;
-mod utils;
-mod game;
+use ttype::{game, menu, results};
 
-use color::Color;
-use pixels::*;
-use color::*;
-use utils::nth_char_idx;
+use ttype::color::*;
+use ttype::keylog::{EventStream, KeyLog};
+use ttype::layout::Layout;
+use ttype::pixels::{HCentering, Pixels, VCentering};
+use ttype::stats::{estimated_time, wpm_over_time, KeyStats, Results, Stats};
+use ttype::theme::Theme;
+use ttype::tui::{enter_tui, leave_tui};
+use ttype::utils::{format_date, is_printable, normalize_file_text};
+
+const DATA_PATH: &str = "static";
+const LANGUAGES_PATH: &str = "languages";
+const QUOTES_PATH: &str = "quotes";
+// Used when the requested word list can't be found, e.g. before the data files are installed
+const FALLBACK_WORDS: &str = include_str!("../static/languages/english.json");
+const DEFAULT_PUNCTUATION: &str = ".,?!;:'\"()-";
+const PUNCTUATION_RATE: f64 = 0.3; // Share of the words that get a punctuation mark
+const NUMBER_RATE: f64 = 0.15; // Share of the words replaced with a number
+const CAPITAL_RATE: f64 = 0.2; // Share of the words that get a capital letter
+const DEFAULT_MAX_FPS: f32 = 60.0;
+// Save and restore the window title (XTWINOPS), as it can't be read back
+const PEEK_HOLD: Duration = Duration::from_millis(600); // How long Ctrl+P shows the whole text, longer than the delay before a held key repeats
+const REJECT_FLASH: Duration = Duration::from_millis(150); // How long the text flashes when --must-correct refuses a space
+const ADAPTIVE_BIAS: f32 = 3.0; // How much --adaptive favors the words with slow keys
+const ESTIMATE_WPM: f32 = 60.0; // Speed used to estimate the length of a test, unless --pace gives one
+const DEFAULT_DIGITS: usize = 3;
+const DEFAULT_LINES: usize = 3; // Lines of wrapped text shown around the caret's unless --lines says otherwise
+const BIGRAM_WORD_RATE: f64 = 0.2; // Share of the --bigrams words taken from the word list instead of made up
+const WORD_COUNTS: [u32; 4] = [10, 25, 50, 100]; // Word counts cycled through on the results screen
+const DURATIONS: [u32; 4] = [15, 30, 60, 120]; // Durations cycled through on the results screen
+
+// How the words of a word list are changed before being typed
+#[derive(Default, Clone)]
+struct WordOptions {
+	punctuation: Vec<char>, // Marks added to some of the words, none if empty
+	numbers: bool, // Replace some of the words with numbers
+	capitals: bool, // Capitalize some of the words
+	min_length: usize, // Shorter words are left out of the word list
+	max_length: Option<usize>, // Longer words are left out of the word list
+	numbers_only: Option<usize>, // Number of digits of the numbers typed instead of words
+	bigrams: Vec<String>, // Letter pairs strung into made-up words by --bigrams, none if empty
+	weights: Option<WeightedIndex<f32>>, // Odds of each word of the list, for --adaptive, all the same if None
+}
 
-const LANGUAGES_PATH: &str = "static/languages";
-const QUOTES_PATH: &str = "static/quotes";
-const UNTYPED_COLOR: Color = Color::new_rgb(80, 80, 80);
-const TYPED_COLOR: Color = Color::new_rgb(255, 255, 255);
-const WRONG_COLOR: Color = Color::new_rgb(255, 0, 0);
 
 #[derive(Debug)]
 enum DictEntry {
@@ -32,73 +62,378 @@ enum DictEntry {
 enum GameMode {
 	CountedWords{number_of_words: u32},
 	TimedWords{time: u32},
+	ListWords{percent: u32}, // Words of the list in shuffled order, each at most once
 	Quote,
 	Text{text: String}
 }
 
-fn used_text_width(twidth: usize) -> usize {
-	let factor = match twidth {
-		0..50 => 0.8,
-		50..100 => 0.7,
-		100..170 => 0.6,
-		_ => 0.5
+impl fmt::Display for GameMode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			GameMode::CountedWords{number_of_words} => write!(f, "{} words", number_of_words),
+			GameMode::TimedWords{time} => write!(f, "{} seconds", time),
+			GameMode::ListWords{percent: 100} => write!(f, "all words"),
+			GameMode::ListWords{percent} => write!(f, "{}% of the words", percent),
+			GameMode::Quote => write!(f, "quote"),
+			GameMode::Text{..} => write!(f, "custom text")
+		}
+	}
+}
+
+fn print_usage(program: &str, opts: Options) {
+	let brief = format!("Usage: {} [options]", program);
+	print!("{}", opts.usage(&brief));
+}
+
+// Names of the dictionaries in a directory, without the files starting with an underscore
+fn list_dicts(dir: &str) -> Vec<String> {
+	let mut names: Vec<String> = fs::read_dir(dir)
+		.unwrap_or_else(|_| panic!("Can't open {}.", dir))
+		.filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+		.filter(|name| !name.starts_with('_'))
+		.filter_map(|name| name.strip_suffix(".json").map(str::to_string))
+		.collect();
+	names.sort();
+	names
+}
+
+// Parse the word list or quote list of a language from its file
+// A missing word list falls back to the built-in English one
+fn read_dict(dir: &str, lang: &str, quotes: bool) -> jzon::JsonValue {
+	let path = format!("{}/{}.json", dir, lang);
+	let contents = match File::open(&path) {
+		Ok(mut file) => {
+			let mut contents = String::new();
+			file.read_to_string(&mut contents).unwrap();
+			contents
+		},
+		Err(_) if !quotes => {
+			eprintln!("Can't open {}, using the built-in English word list instead.", path);
+			FALLBACK_WORDS.to_string()
+		},
+		Err(_) => panic!("That language doesn't exist.")
 	};
-	return (twidth as f32 * factor) as usize;
+	jzon::parse(&contents).unwrap()
+}
+
+// Word list or quote list of a language picked during the session, None if it can't be loaded
+// Unlike read_dict this never exits, the player stays on the current language instead
+fn try_read_dict(dir: &str, lang: &str, quotes: bool) -> Option<jzon::JsonValue> {
+	let contents = fs::read_to_string(format!("{}/{}.json", dir, lang)).ok()?;
+	let parsed = jzon::parse(&contents).ok()?;
+	let key = if quotes { "quotes" } else { "words" };
+	(parsed[key].is_array() && !parsed[key].is_empty()).then_some(parsed)
 }
 
-fn correct_combine(typed: &str, text: &str) -> ColoredText {
-	let mut out = ColoredText::new();
+// Apply the "defaults" object of a word or quote list to the options its language wants
+// Options given on the command line win, and unknown keys are ignored so newer lists still load
+fn apply_defaults(parsed: &jzon::JsonValue, quotes: bool, word_options: &mut WordOptions, settings: &mut game::Settings, given: impl Fn(&str) -> bool) {
+	let defaults = &parsed["defaults"];
+	let flag = |key: &str, option: &str| if given(option) { None } else { defaults[key].as_bool() };
 	
-	let typed_words: Vec<&str> = typed.split_whitespace().collect();
-	let text_words: Vec<&str> = text.split_whitespace().collect();
+	if let Some(on) = flag("strict_space", "strict-space") {
+		settings.strict_space = on;
+	}
+	if let Some(on) = flag("single_line", "single-line") {
+		settings.single_line = on;
+	}
+	// A difficulty picked on the command line sets all the word options
+	if quotes || given("difficulty") {
+		return;
+	}
 	
-	for i in 0..text_words.len() {
-		if i < typed_words.len() {
-			let text_chars: Vec<char> = text_words[i].chars().collect();
-			let typed_chars: Vec<char> = typed_words[i].chars().collect();
-			
-			for j in 0..text_chars.len() {
-				if typed_chars.get(j).unwrap_or(&' ').to_owned() == text_chars[j].to_owned() {
-					out.push(typed_chars[j], TYPED_COLOR, false, false);
-				} else if let Some(c) = typed_chars.get(j) {
-					out.push(c.to_owned(), WRONG_COLOR, false, true);
-				} else {
-					out.push(text_chars[j], UNTYPED_COLOR, false, false);
-				}
+	if !given("punctuation") && !given("punct-set") {
+		match &defaults["punctuation"] {
+			jzon::JsonValue::Boolean(true) => word_options.punctuation = DEFAULT_PUNCTUATION.chars().collect(),
+			jzon::JsonValue::Boolean(false) => word_options.punctuation = Vec::new(),
+			set => if let Some(set) = set.as_str() {
+				word_options.punctuation = set.chars().filter(|c| !c.is_whitespace()).collect();
 			}
+		}
+	}
+	if let Some(on) = flag("numbers", "numbers") {
+		word_options.numbers = on;
+	}
+	if let Some(on) = flag("capitals", "capitals") {
+		word_options.capitals = on;
+	}
+	if let (false, Some(length)) = (given("min-word-length"), defaults["min_word_length"].as_usize()) {
+		word_options.min_length = length;
+	}
+	if let (false, Some(length)) = (given("max-word-length"), defaults["max_word_length"].as_usize()) {
+		word_options.max_length = Some(length);
+	}
+}
+
+// A word of a list without stray whitespace or invisible characters, which would desync the caret
+fn clean_word(word: &str) -> String {
+	word.chars().filter(|c| is_printable(*c)).collect::<String>().trim().to_string()
+}
+
+// Words of a parsed word list that had to be cleaned up, written with their invisible characters escaped
+fn invalid_words(parsed: &jzon::JsonValue) -> Vec<String> {
+	parsed["words"].members().filter_map(|word| word.as_str()).filter(|word| clean_word(word) != *word)
+		.map(|word| format!("\"{}\"", word.chars().map(|c| if is_printable(c) { c.to_string() } else { c.escape_unicode().to_string() }).collect::<String>()))
+		.collect()
+}
+
+// Entries of a parsed word list or quote list, quotes can be restricted to an author
+// Errors are a sentence telling why the list can't be used, so a list picked during the session can be turned down
+fn parse_dict(parsed: &jzon::JsonValue, quotes: bool, author: Option<&str>, word_options: &WordOptions) -> Result<Vec<DictEntry>, String> {
+	let mut dict: Vec<DictEntry> = Vec::new();
+	if quotes {
+		let list = parsed["quotes"].as_array().ok_or("The list has no quotes.")?;
+		for quote in list {
+			match (quote["text"].as_str(), quote["source"].as_str()) {
+				(Some(text), Some(source)) => dict.push(DictEntry::Quote{text: text.to_string(), source: source.to_string()}),
+				_ => return Err("A quote of the list has no text or no source.".to_string())
+			}
+		}
+		
+		if let Some(name) = author {
+			let name = name.to_lowercase();
+			let mut sources: Vec<String> = dict.iter().filter_map(|entry| match entry {
+				DictEntry::Quote{source, ..} => Some(source.clone()),
+				_ => None
+			}).collect();
+			
+			dict.retain(|entry| matches!(entry, DictEntry::Quote{source, ..} if source.to_lowercase().contains(&name)));
 			
-			// This is for when the player types longer than the word
-			for j in text_chars.len()..typed_chars.len() {
-				out.push(typed_chars[j], WRONG_COLOR, false, true);
+			if dict.is_empty() {
+				sources.sort();
+				sources.dedup();
+				return Err(format!("No quote comes from {}. Available sources are: {}", name, sources.join(", ")));
+			}
+		}
+	} else {
+		// Words are cleaned up, see invalid_words for the ones that needed it
+		// A word listed twice is only kept once, so it isn't picked more often or typed twice by -w all
+		let mut seen = HashSet::new();
+		for word in parsed["words"].as_array().ok_or("The list has no words.")? {
+			let clean = clean_word(word.as_str().ok_or("A word of the list isn't text.")?);
+			let length = clean.chars().count();
+			if !clean.is_empty() && length >= word_options.min_length && word_options.max_length.is_none_or(|max| length <= max) && seen.insert(clean.clone()) {
+				dict.push(DictEntry::Word(clean));
 			}
-		} else {
-			out.push_str(text_words[i], UNTYPED_COLOR, false, false);
 		}
-		out.push(' ', UNTYPED_COLOR, false, false);
+		if dict.is_empty() {
+			return Err("No word of the list has a length allowed by the difficulty or word length options.".to_string());
+		}
 	}
-	
-	out
+	Ok(dict)
 }
 
-fn show_cursor(to_print: &mut ColoredText, typed: &str, text: &str, cursor_pos: usize) {
-	let typed_words: Vec<&str> = typed.split_whitespace().collect();
-	let text_words: Vec<&str> = text.split_whitespace().collect();
-	
-	let mut diff = 0;
+// Word or quote list of a language picked during the session, with the options its defaults give
+// The defaults are applied over the options of the command line, not over the ones of the previous language
+fn switch_dict(dir: &str, name: &str, quotes: bool, author: Option<&str>, base: (&WordOptions, &game::Settings), given: impl Fn(&str) -> bool) -> Result<(Vec<DictEntry>, WordOptions, game::Settings), String> {
+	let parsed = try_read_dict(dir, name, quotes).ok_or(format!("{} can't be loaded.", name))?;
+	let (mut word_options, mut settings) = (base.0.clone(), *base.1);
+	apply_defaults(&parsed, quotes, &mut word_options, &mut settings, given);
+	let dict = parse_dict(&parsed, quotes, author, &word_options)?;
+	Ok((dict, word_options, settings))
+}
+
+// Get the contents at a URL
+#[cfg(feature = "url")]
+fn download(url: &str) -> String {
+	ureq::get(url).call()
+		.unwrap_or_else(|e| panic!("Can't download {}: {}", url, e))
+		.into_string()
+		.unwrap_or_else(|e| panic!("Can't read {}: {}", url, e))
+}
+
+#[cfg(not(feature = "url"))]
+fn download(_url: &str) -> String {
+	panic!("--url needs ttype to be built with the url feature (cargo build --features url).");
+}
+
+// Get the text in the clipboard
+#[cfg(feature = "clipboard")]
+fn read_clipboard() -> String {
+	arboard::Clipboard::new()
+		.and_then(|mut clipboard| clipboard.get_text())
+		.unwrap_or_else(|e| panic!("Can't read the clipboard: {}", e))
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn read_clipboard() -> String {
+	panic!("--clipboard needs ttype to be built with the clipboard feature (cargo build --features clipboard).");
+}
+
+// Word options bundled by a --difficulty
+fn difficulty(name: &str) -> Option<WordOptions> {
+	let punctuation = DEFAULT_PUNCTUATION.chars().collect();
+	match name {
+		"easy" => Some(WordOptions { max_length: Some(5), ..WordOptions::default() }),
+		"normal" => Some(WordOptions::default()),
+		"hard" => Some(WordOptions { punctuation, numbers: true, ..WordOptions::default() }),
+		"expert" => Some(WordOptions { punctuation, numbers: true, capitals: true, min_length: 6, ..WordOptions::default() }),
+		_ => None
+	}
+}
+
+// Turn some words into numbers, capitalize some and add a random punctuation mark from the options to some
+// Brackets and quotes go around the word
+fn decorate(word: &str, options: &WordOptions, rng: &mut impl Rng) -> String {
+	let mut word = word.to_string();
+	if options.numbers && rng.random_bool(NUMBER_RATE) {
+		word = rng.random_range(0..10000).to_string();
+	}
+	if options.capitals && rng.random_bool(CAPITAL_RATE) {
+		let mut chars = word.chars();
+		word = chars.next().map_or(String::new(), |c| c.to_uppercase().chain(chars).collect());
+	}
+	if options.punctuation.is_empty() || !rng.random_bool(PUNCTUATION_RATE) {
+		return word;
+	}
 	
-	let space_terminated = typed.chars().last().unwrap_or('a').eq(&' ');
-	if typed_words.len() > if space_terminated {0} else {1} {
-		for i in 0..(typed_words.len() - if space_terminated {0} else {1}) {
-			diff += max(text_words[i].len() as i32 - typed_words[i].len() as i32, 0);
+	match options.punctuation[rng.random_range(0..options.punctuation.len())] {
+		'(' | ')' => format!("({})", word),
+		'[' | ']' => format!("[{}]", word),
+		'{' | '}' => format!("{{{}}}", word),
+		'<' | '>' => format!("<{}>", word),
+		'"' => format!("\"{}\"", word),
+		'\'' => format!("'{}'", word),
+		mark => format!("{}{}", word, mark)
+	}
+}
+
+// Random words from the word list, or random numbers with --numbers-only, separated by spaces
+// --bigrams makes up most of the words from 2 or 3 of its letter pairs
+fn random_words(count: usize, dict: &[DictEntry], options: &WordOptions, rng: &mut impl Rng) -> String {
+	let mut text = String::new();
+	for _ in 0..count {
+		if let Some(digits) = options.numbers_only {
+			let number: String = (0..digits).map(|_| char::from(b'0' + rng.random_range(0..10))).collect();
+			text.push_str(&decorate(&number, options, rng));
+			text.push(' ');
+		} else if !options.bigrams.is_empty() && !rng.random_bool(BIGRAM_WORD_RATE) {
+			let pairs = rng.random_range(2..=3);
+			let word: String = (0..pairs).map(|_| options.bigrams[rng.random_range(0..options.bigrams.len())].as_str()).collect();
+			text.push_str(&decorate(&word, options, rng));
+			text.push(' ');
+		} else if let DictEntry::Word(w) = &dict[match &options.weights {
+			Some(weights) => weights.sample(rng),
+			None => rng.random_range(0..dict.len())
+		}] {
+			text.push_str(&decorate(w, options, rng));
+			text.push(' ');
 		}
 	}
-	
-	to_print.set_underline((cursor_pos as i32 + diff) as usize);
+	text
 }
 
-fn print_usage(program: &str, opts: Options) {
-	let brief = format!("Usage: {} [options]", program);
-	print!("{}", opts.usage(&brief));
+// Odds of each word for --adaptive, words made of keys typed slowly coming up more often
+// A word's weight is the average slowness of its characters raised to ADAPTIVE_BIAS, unknown keys counting as average
+fn adaptive_weights(dict: &[DictEntry], slowness: &HashMap<char, f32>) -> Option<WeightedIndex<f32>> {
+	if slowness.is_empty() {
+		return None;
+	}
+	let weights = dict.iter().map(|entry| match entry {
+		DictEntry::Word(w) => {
+			let total: f32 = w.chars().map(|c| *slowness.get(&c.to_lowercase().next().unwrap_or(c)).unwrap_or(&1.0)).sum();
+			(total / w.chars().count().max(1) as f32).powf(ADAPTIVE_BIAS)
+		},
+		DictEntry::Quote{..} => 0.0
+	});
+	WeightedIndex::new(weights).ok()
+}
+
+// Number of words of the list taken by -w all or a percentage, rounded down
+fn list_word_count(percent: u32, dict: &[DictEntry]) -> usize {
+	dict.len() * percent as usize / 100
+}
+
+// Words of the list in a random order without repeats, as many as the percentage of the list asks for
+fn list_words(percent: u32, dict: &[DictEntry], options: &WordOptions, rng: &mut impl Rng) -> String {
+	let mut text = String::new();
+	for i in rand::seq::index::sample(rng, dict.len(), list_word_count(percent, dict)) {
+		if let DictEntry::Word(w) = &dict[i] {
+			text.push_str(&decorate(w, options, rng));
+			text.push(' ');
+		}
+	}
+	text
+}
+
+// Mode picked with a key on the results screen: w for words, d for time, q for quotes
+// t cycles through the counts of the words or time mode
+fn switch_mode(mode: &GameMode, key: char) -> Option<GameMode> {
+	let next = |counts: &[u32], current: u32| counts[(counts.iter().position(|&c| c == current).map_or(0, |i| i + 1)) % counts.len()];
+	match (key, mode) {
+		('w', GameMode::CountedWords{..}) | ('d', GameMode::TimedWords{..}) | ('q', GameMode::Quote) => None,
+		('w', _) => Some(GameMode::CountedWords{number_of_words: WORD_COUNTS[1]}),
+		('d', _) => Some(GameMode::TimedWords{time: DURATIONS[1]}),
+		('q', _) => Some(GameMode::Quote),
+		('t', GameMode::CountedWords{number_of_words}) => Some(GameMode::CountedWords{number_of_words: next(&WORD_COUNTS, *number_of_words)}),
+		('t', GameMode::TimedWords{time}) => Some(GameMode::TimedWords{time: next(&DURATIONS, *time)}),
+		_ => None
+	}
+}
+
+// Line of the results screen showing the modes, the current one highlighted
+// Quotes are only offered when the language has some
+fn mode_options(mode: &GameMode, language: Option<&str>, has_quotes: bool, theme: &Theme) -> ColoredText {
+	let options = [
+		("[w] words", matches!(mode, GameMode::CountedWords{..} | GameMode::ListWords{..}), true),
+		("[d] time", matches!(mode, GameMode::TimedWords{..}), true),
+		("[q] quote", *mode == GameMode::Quote, has_quotes),
+	];
+	let mut line = ColoredText::new();
+	for (name, current, _) in options.into_iter().filter(|&(_, current, shown)| current || shown) {
+		line.push_str(&format!("{}  ", name), if current { theme.caret } else { theme.untyped }, false, current);
+	}
+	if matches!(mode, GameMode::CountedWords{..} | GameMode::TimedWords{..}) {
+		line.push_str(&format!("[t] {}  ", mode), theme.untyped, false, false);
+	}
+	if let Some(language) = language {
+		line.push_str(&format!("[l] {}", language), theme.untyped, false, false);
+	}
+	line
+}
+
+// Text to type in the given game mode, along with the sources of its quotes
+fn generate_text(game_mode: &GameMode, dict: &[DictEntry], options: &WordOptions, rng: &mut impl Rng) -> (String, Vec<String>) {
+	match game_mode {
+		GameMode::CountedWords{number_of_words} => (random_words(*number_of_words as usize, dict, options, rng), Vec::new()),
+		GameMode::TimedWords{..} => (random_words(100, dict, options, rng), Vec::new()),
+		GameMode::ListWords{percent} => (list_words(*percent, dict, options, rng), Vec::new()),
+		GameMode::Quote => {
+			let (text, source) = random_quote(dict, rng);
+			(text, vec![source])
+		},
+		GameMode::Text{text} => (text.clone(), Vec::new())
+	}
+}
+
+// Pick a random quote, returns its text and source
+fn random_quote(dict: &[DictEntry], rng: &mut impl Rng) -> (String, String) {
+	quote_text(&dict[rng.random_range(0..dict.len())])
+}
+
+// Text and source of a quote
+fn quote_text(entry: &DictEntry) -> (String, String) {
+	match entry {
+		DictEntry::Quote{text, source} => (text.clone(), source.clone()),
+		DictEntry::Word(w) => (w.clone(), String::new())
+	}
+}
+
+// Order of the quotes of --quote-chain, every quote comes once before they're shuffled again
+#[derive(Default)]
+struct QuoteDeck {
+	order: Vec<usize>, // Indexes in the list of the quotes left, the next one last
+}
+
+impl QuoteDeck {
+	fn draw(&mut self, dict: &[DictEntry], rng: &mut impl Rng) -> (String, String) {
+		self.order.retain(|&i| i < dict.len());
+		if self.order.is_empty() {
+			self.order = (0..dict.len()).collect();
+			self.order.shuffle(rng);
+		}
+		quote_text(&dict[self.order.pop().unwrap()])
+	}
 }
 
 fn main() {
@@ -108,11 +443,69 @@ fn main() {
 	let mut opts = Options::new();
 	
 	opts.optopt("l", "lang", "which language to use", "LANGUAGE");
-	opts.optopt("w", "words", "use the provided number of words", "INTEGER");
+	opts.optflag("", "list-languages", "print the available languages, or quote lists with -q, and exit");
+	opts.optflag("", "menu", "pick the language from a list when -l is omitted");
+	opts.optopt("w", "words", "use the provided number of words, comma separated numbers for a test in stages, or all or a percentage like 50% to type that much of the list once each", "INTEGER");
 	opts.optopt("d", "duration", "play for the provided duration", "SECONDS");
+	opts.optopt("", "target-time", "type as many words as should take the provided time", "SECONDS");
+	opts.optopt("", "assume-wpm", "speed --target-time counts the words at (default 60)", "WPM");
 	opts.optflag("q", "quotes", "use quotes");
+	opts.optopt("a", "author", "only use quotes whose source contains NAME", "NAME");
+	opts.optflag("", "quote-chain", "start another quote as soon as one is finished");
+	opts.optflag("", "punctuation", "add punctuation to the words");
+	opts.optopt("", "punct-set", "add punctuation to the words, using only the provided marks", "MARKS");
+	opts.optflag("", "numbers", "replace some of the words with numbers");
+	opts.optflag("", "capitals", "capitalize some of the words");
+	opts.optflag("", "numbers-only", "type random numbers instead of words, for numpad practice");
+	opts.optopt("", "bigrams", "drill comma separated letter pairs, strung into made-up words between a few real ones", "PAIRS");
+	opts.optopt("", "digits", "number of digits of each number with --numbers-only (default 3)", "LENGTH");
+	opts.optopt("", "min-word-length", "only use words with at least the provided number of letters", "LENGTH");
+	opts.optopt("", "max-word-length", "only use words with at most the provided number of letters", "LENGTH");
+	opts.optopt("", "difficulty", "preset of the word options: easy, normal, hard or expert (default normal)", "LEVEL");
 	opts.optopt("t", "text", "use provided text", "TEXT");
 	opts.optopt("f", "file", "use text from provided file", "PATH");
+	opts.optflag("", "clipboard", "use the text in the clipboard");
+	opts.optflag("", "lossy", "replace the characters of a file that aren't valid UTF-8 instead of exiting");
+	opts.optopt("", "data-dir", "directory holding the languages and quotes folders", "PATH");
+	opts.optopt("", "seed", "pick the same words and quotes, in the same order, on every run with the same seed", "NUMBER");
+	opts.optopt("", "url", "download the word list or quotes to use", "URL");
+	opts.optflag("", "single-line", "scroll the text on a single line instead of wrapping it");
+	opts.optopt("", "lines", "show the provided number of lines around the caret's, 3 by default keeps one line of context on each side, 0 or --show-context shows them all, hold Ctrl+P to see them all", "LINES");
+	opts.optopt("", "columns", "lay the wrapped lines out in the provided number of columns, for wide terminals", "COLUMNS");
+	opts.optflag("", "mini", "draw the text on a single row with its speed, for a small pane");
+	opts.optflag("", "fixed-caret", "keep the caret in place and scroll the text under it");
+	opts.optflag("", "strict-space", "require spaces to be typed exactly as in the text");
+	opts.optflag("", "skip-indent", "with --strict-space, type the indentation of each line for you, for code");
+	opts.optflag("", "highlight-word", "give the word being typed a faint background");
+	opts.optflag("", "smooth-scroll", "ease the text to its new line when --fixed-caret scrolls");
+	opts.optopt("", "warmup", "type the provided number of words untimed before each test", "WORDS");
+	opts.optflag("", "must-correct", "refuse the space ending a word until its mistakes are fixed");
+	opts.optflag("", "death", "end the test at the first wrong key");
+	opts.optflag("", "show-context", "dim the lines of the text away from the caret");
+	opts.optflagopt("", "show-spaces", "draw the spaces between words as faint dots, \"all\" keeps them on the typed spaces too", "all");
+	opts.optflag("", "bold", "draw all the text in bold");
+	opts.optflag("", "border", "draw a box around the text");
+	opts.optopt("", "idle-timeout", "stop the timer after the provided time without typing, 0 to never stop it (default 10)", "SECONDS");
+	opts.optopt("", "pace", "show a ghost caret moving at the provided speed", "WPM");
+	opts.optopt("", "max-fps", "redraw at most the provided number of times per second (default 60)", "FPS");
+	opts.optflag("", "set-title", "show the live speed in the terminal window title");
+	opts.optopt("", "remap", "practice a layout (dvorak, colemak or a JSON file) on a QWERTY keyboard", "LAYOUT");
+	opts.optopt("", "theme", "which theme to use (default, high-contrast)", "THEME");
+	opts.optopt("", "palette-file", "JSON list of the terminal's colors, for more accurate colors without true color support", "PATH");
+	opts.optopt("", "caret-color", "color of the caret", "HEX");
+	opts.optflag("", "no-caret", "don't draw the caret, only the colors of the text show where you are");
+	opts.optopt("", "record", "save the key presses of the last finished run to the provided file, each run replacing the previous one", "PATH");
+	opts.optopt("", "replay", "replay a run saved with --record", "PATH");
+	opts.optflag("", "key-stats", "print the accuracy and speed of each key when quitting");
+	opts.optflag("", "split-case", "count upper and lower case keys separately in --key-stats");
+	opts.optopt("", "export", "write a text card with the results of the last run to the provided file", "PATH");
+	opts.optflag("", "show-width", "show a ruler of the text width, Ctrl+W toggles it during a test");
+	opts.optflag("", "json", "print the results of the last run as JSON when quitting, or --list-languages as JSON");
+	opts.optopt("", "event-stream", "write each key press as a JSON line to a file or file descriptor while typing", "FILE|FD");
+	opts.optflag("", "adaptive", "pick more of the words with the keys you type slowly, from --history and the current session");
+	opts.optopt("", "history", "append the results of each finished run to the provided JSON lines file", "PATH");
+	opts.optflag("", "dump-text", "print the text to type and exit");
+	opts.optflag("", "verbose", "print a backtrace if ttype crashes");
 	opts.optflag("h", "help", "print this help menu");
 	let matches = match opts.parse(&args[1..]) {
 		Ok(m) => { m }
@@ -124,11 +517,26 @@ fn main() {
 		return;
 	}
 	
-	let lang = match matches.opt_str("l") {
-		Some(l) => l,
-		None => "english".to_string()
-	};
+	// This will be called on a panic so the terminal doesn't stay all messed up
+	// It's set before anything changes the terminal, the --menu picker included, so errors in the options go to stderr too
+	// The backtrace is only useful to debug ttype, players just get the error
+	let verbose = matches.opt_present("verbose") || env::var_os("RUST_BACKTRACE").is_some_and(|v| v != "0");
+	panic::set_hook(Box::new(move |info| {
+		leave_tui();
+		
+		if verbose {
+			eprintln!("{}", std::backtrace::Backtrace::force_capture());
+			eprintln!("{}", info);
+		} else if let Some(message) = info.payload().downcast_ref::<&str>() {
+			eprintln!("Error: {}", message);
+		} else if let Some(message) = info.payload().downcast_ref::<String>() {
+			eprintln!("Error: {}", message);
+		} else {
+			eprintln!("{}", info);
+		}
+	}));
 	
+	let mut stages: Vec<u32> = Vec::new(); // Number of words of each stage of the test
 	let game_mode = {
 		let mut selected = false;
 		let mut game_mode = GameMode::CountedWords{number_of_words: 30};
@@ -144,10 +552,27 @@ fn main() {
 			selected = true;
 		}
 		if let Some(path) = matches.opt_str("f") {
-			let mut file = File::open(&path).expect(&format!("Can't open {}. Does the file exist ?", path));
-			let mut contents = String::new();
-			file.read_to_string(&mut contents).unwrap();
-			game_mode = GameMode::Text{text: contents};
+			let bytes = fs::read(&path).unwrap_or_else(|_| panic!("Can't open {}. Does the file exist ?", path));
+			let contents = match String::from_utf8(bytes) {
+				Ok(contents) => contents,
+				Err(e) if matches.opt_present("lossy") => {
+					eprintln!("{} is not valid UTF-8, invalid characters were replaced.", path);
+					String::from_utf8_lossy(e.as_bytes()).into_owned()
+				},
+				Err(_) => panic!("{} is not valid UTF-8, use --lossy to replace the invalid characters.", path)
+			};
+			game_mode = GameMode::Text{text: normalize_file_text(&contents)};
+			if selected {
+				panic!("Only one game mode can be selected at a time.");
+			}
+			selected = true;
+		}
+		if matches.opt_present("clipboard") {
+			let text = normalize_file_text(&read_clipboard());
+			if text.trim().is_empty() {
+				panic!("The clipboard is empty.");
+			}
+			game_mode = GameMode::Text{text};
 			if selected {
 				panic!("Only one game mode can be selected at a time.");
 			}
@@ -160,8 +585,32 @@ fn main() {
 			}
 			selected = true;
 		}
+		if let Some(seconds) = matches.opt_str("target-time") {
+			// A words test long enough to last about that time at the assumed speed
+			let seconds: f32 = seconds.parse().ok().filter(|s: &f32| *s > 0.0).unwrap_or_else(|| panic!("{} is not a valid time.", seconds));
+			let wpm: f32 = matches.opt_str("assume-wpm").map_or(ESTIMATE_WPM, |wpm| wpm.parse().ok().filter(|w: &f32| *w > 0.0).unwrap_or_else(|| panic!("{} is not a valid speed.", wpm)));
+			game_mode = GameMode::CountedWords{number_of_words: ((seconds / 60.0 * wpm).round() as u32).max(1)};
+			if selected {
+				panic!("Only one game mode can be selected at a time.");
+			}
+			selected = true;
+		} else if matches.opt_present("assume-wpm") {
+			panic!("--assume-wpm can only be used with --target-time.");
+		}
 		if let Some(w) = matches.opt_str("w") {
-			game_mode = GameMode::CountedWords{number_of_words: w.parse().unwrap()};
+			// The whole list or a percentage of it is typed once each, several comma separated counts make a test in stages
+			if w == "all" {
+				game_mode = GameMode::ListWords{percent: 100};
+			} else if let Some(percent) = w.strip_suffix('%') {
+				let percent = percent.trim().parse().ok().filter(|p| (1..=100).contains(p)).unwrap_or_else(|| panic!("{} is not a valid percentage of the words.", w));
+				game_mode = GameMode::ListWords{percent};
+			} else {
+				let counts: Vec<u32> = w.split(',').map(|n| n.trim().parse().unwrap_or_else(|_| panic!("{} is not a valid number of words.", n))).collect();
+				game_mode = GameMode::CountedWords{number_of_words: counts[0]};
+				if counts.len() > 1 {
+					stages = counts;
+				}
+			}
 			if selected {
 				panic!("Only one game mode can be selected at a time.");
 			}
@@ -169,206 +618,645 @@ fn main() {
 		game_mode
 	};
 	
-	let dict_dir = if cfg!(debug_assertions) {
-		env::var("CARGO_MANIFEST_DIR").unwrap()
-	} else {
+	// Word lists and quotes can be downloaded, the list's top-level key tells which one it is
+	let downloaded = matches.opt_str("url").map(|url| {
+		let parsed = jzon::parse(&download(&url)).unwrap_or_else(|_| panic!("{} is not a valid word list or quote list.", url));
+		if !parsed.has_key("words") && !parsed.has_key("quotes") {
+			panic!("{} is not a valid word list or quote list.", url);
+		}
+		if parsed.has_key("words") && game_mode == GameMode::Quote {
+			panic!("{} has no quotes.", url);
+		}
+		parsed
+	});
+	let mut game_mode = match &downloaded {
+		Some(parsed) if parsed.has_key("quotes") => {
+			if matches.opt_present("w") || matches.opt_present("d") {
+				panic!("A quote list can't be used with -w or -d.");
+			}
+			GameMode::Quote
+		},
+		_ => game_mode
+	};
+	
+	let mut theme = match matches.opt_str("theme") {
+		Some(name) => Theme::from_name(&name).unwrap_or_else(|| panic!("There is no theme called {}.", name)),
+		None => Theme::default()
+	};
+	if let Some(path) = matches.opt_str("palette-file") {
+		load_palette(&path);
+	}
+	if let Some(hex) = matches.opt_str("caret-color") {
+		theme.caret = Color::new_hex(&hex);
+	}
+	
+	let mut settings = game::Settings {
+		single_line: matches.opt_present("single-line") || matches.opt_present("mini"),
+		strict_space: matches.opt_present("strict-space"),
+		fixed_caret: matches.opt_present("fixed-caret"),
+		bold: matches.opt_present("bold"),
+		border: matches.opt_present("border") && !matches.opt_present("mini"),
+		pace: matches.opt_str("pace").map(|wpm| wpm.parse().unwrap_or_else(|_| panic!("{} is not a valid speed.", wpm))),
+		show_context: matches.opt_present("show-context"),
+		inverse_caret: false,
+		show_spaces: matches.opt_present("show-spaces"),
+		show_typed_spaces: match matches.opt_str("show-spaces").as_deref() {
+			None => false,
+			Some("all") => true,
+			Some(other) => panic!("{} is not a valid value for --show-spaces, the only one is all.", other)
+		},
+		smooth_scroll: matches.opt_present("smooth-scroll"),
+		highlight_word: matches.opt_present("highlight-word"),
+		hide_caret: matches.opt_present("no-caret"),
+		mini: matches.opt_present("mini"),
+		skip_indent: matches.opt_present("skip-indent"),
+		columns: matches.opt_str("columns").map_or(1, |n| n.parse().ok().filter(|&n| n > 0).unwrap_or_else(|| panic!("{} is not a valid number of columns.", n))),
+		lines: matches.opt_str("lines").map(|n| n.parse().unwrap_or_else(|_| panic!("{} is not a valid number of lines.", n))).filter(|&n| n > 0),
+	};
+	
+	if matches.opt_present("a") && game_mode != GameMode::Quote {
+		panic!("--author can only be used with quotes.");
+	}
+	let quote_chain = matches.opt_present("quote-chain");
+	if quote_chain && game_mode != GameMode::Quote {
+		panic!("--quote-chain can only be used with quotes.");
+	}
+	if let GameMode::Text{text} = &game_mode {
+		if !settings.strict_space && text.lines().any(|line| line.trim().contains("  ")) {
+			eprintln!("The text has runs of spaces, they are typed as a single space. Use --strict-space to type each of them.");
+		}
+	}
+	if settings.lines.is_some() && settings.single_line {
+		panic!("--lines can't be used with --single-line or --mini.");
+	}
+	if settings.columns > 1 && (settings.single_line || settings.fixed_caret || settings.lines.is_some()) {
+		panic!("--columns can't be used with --single-line, --mini, --fixed-caret or --lines.");
+	}
+	if settings.skip_indent && !settings.strict_space {
+		panic!("--skip-indent can only be used with --strict-space, the indentation isn't typed otherwise.");
+	}
+	if settings.smooth_scroll && !settings.fixed_caret {
+		panic!("--smooth-scroll can only be used with --fixed-caret.");
+	}
+	let warmup_words = matches.opt_str("warmup").map_or(0, |n| n.parse::<usize>().unwrap_or_else(|_| panic!("{} is not a valid number of words.", n)));
+	// Wrapped text is shown a few lines at a time, the single line and the columns have their own layouts
+	// --show-context dims the lines away from the caret instead, as the Ctrl+P peek does
+	if !matches.opt_present("lines") && !settings.single_line && settings.columns <= 1 && !settings.show_context {
+		settings.lines = Some(DEFAULT_LINES);
+	}
+	
+	// The difficulty only gives defaults, each option given on its own wins over it
+	let difficulty_name = matches.opt_str("difficulty").unwrap_or("normal".to_string());
+	let mut word_options = difficulty(&difficulty_name).unwrap_or_else(|| panic!("{} is not a difficulty, use easy, normal, hard or expert.", difficulty_name));
+	match matches.opt_str("punct-set") {
+		Some(set) if set.trim().is_empty() => panic!("--punct-set needs at least one punctuation mark."),
+		Some(set) => word_options.punctuation = set.chars().filter(|c| !c.is_whitespace()).collect(),
+		None if matches.opt_present("punctuation") => word_options.punctuation = DEFAULT_PUNCTUATION.chars().collect(),
+		None => {}
+	}
+	word_options.numbers |= matches.opt_present("numbers");
+	word_options.capitals |= matches.opt_present("capitals");
+	let parse_length = |name: &str| matches.opt_str(name).map(|n| n.parse::<usize>().unwrap_or_else(|_| panic!("{} is not a valid word length.", n)));
+	if let Some(length) = parse_length("min-word-length") {
+		word_options.min_length = length;
+	}
+	if let Some(length) = parse_length("max-word-length") {
+		word_options.max_length = Some(length);
+	}
+	if matches.opt_present("numbers-only") {
+		let digits = parse_length("digits").unwrap_or(DEFAULT_DIGITS);
+		if digits == 0 {
+			panic!("Numbers need at least one digit.");
+		}
+		word_options.numbers_only = Some(digits);
+	}
+	if let Some(list) = matches.opt_str("bigrams") {
+		if word_options.numbers_only.is_some() {
+			panic!("--bigrams can't be used with --numbers-only.");
+		}
+		word_options.bigrams = list.split(',').map(|pair| pair.trim().to_string()).collect();
+		if let Some(pair) = word_options.bigrams.iter().find(|pair| pair.chars().count() != 2) {
+			panic!("\"{}\" is not a letter pair, --bigrams takes pairs like th,he,in.", pair);
+		}
+	}
+	let changes_words = ["difficulty", "punctuation", "punct-set", "numbers", "capitals", "min-word-length", "max-word-length", "numbers-only", "bigrams", "warmup", "adaptive"].iter().any(|o| matches.opt_present(o));
+	if changes_words && !matches!(game_mode, GameMode::CountedWords{..} | GameMode::TimedWords{..} | GameMode::ListWords{..}) {
+		panic!("The difficulty and word options can only be used with word lists.");
+	}
+	
+	let layout = matches.opt_str("remap").map(|name| Layout::from_name(&name).unwrap_or_else(|| Layout::load(&name)));
+	
+	let idle_timeout = match matches.opt_str("idle-timeout") {
+		Some(s) => s.parse::<f32>().unwrap_or_else(|_| panic!("{} is not a valid duration.", s)),
+		None => 10.0
+	};
+	let idle_timeout = if idle_timeout > 0.0 { Some(Duration::from_secs_f32(idle_timeout)) } else { None };
+	
+	let max_fps = match matches.opt_str("max-fps") {
+		Some(s) => s.parse::<f32>().ok().filter(|&fps| fps > 0.0).unwrap_or_else(|| panic!("{} is not a valid frame rate.", s)),
+		None => DEFAULT_MAX_FPS
+	};
+	let frame_duration = Duration::from_secs_f32(1.0 / max_fps);
+	
+	let record_path = matches.opt_str("record");
+	let replay_log = matches.opt_str("replay").map(|path| KeyLog::load(&path));
+	
+	let data_dir = match matches.opt_str("data-dir") {
+		Some(dir) => {
+			for sub in [LANGUAGES_PATH, QUOTES_PATH] {
+				if !fs::metadata(format!("{}/{}", dir, sub)).is_ok_and(|m| m.is_dir()) {
+					panic!("{} must be a directory containing {} and {} folders.", dir, LANGUAGES_PATH, QUOTES_PATH);
+				}
+			}
+			dir
+		},
+		None if cfg!(debug_assertions) => format!("{}/{}", env::var("CARGO_MANIFEST_DIR").unwrap(), DATA_PATH),
 		//TODO: find where to put the dictionnaries for installs
-		"".to_string()
+		None => format!("/{}", DATA_PATH)
 	};
 	
-	let dict = {
-		let path = format!("{}/{}/{}.json", dict_dir, if game_mode == GameMode::Quote { QUOTES_PATH } else { LANGUAGES_PATH }, lang);
-		let mut file = File::open(&path).expect("That language doesn't exist.");
-		let mut contents = String::new();
-		file.read_to_string(&mut contents).unwrap();
-		let parsed = jzon::parse(&mut contents).unwrap();
-		let mut dict: Vec<DictEntry> = Vec::new();
-		if game_mode == GameMode::Quote {
-			for quote in parsed["quotes"].as_array().unwrap() {
-				dict.push(DictEntry::Quote{
-					text: quote["text"].as_str().unwrap().to_string(),
-					source: quote["source"].as_str().unwrap().to_string()
-				});
+	let dict_path = format!("{}/{}", data_dir, if game_mode == GameMode::Quote { QUOTES_PATH } else { LANGUAGES_PATH });
+	
+	if matches.opt_present("list-languages") {
+		let names = list_dicts(&dict_path);
+		if matches.opt_present("json") {
+			// Each file has to be parsed for its count, which is still fast for the bundled lists
+			let (key, count_name) = if game_mode == GameMode::Quote { ("quotes", "quote_count") } else { ("words", "word_count") };
+			let mut list = jzon::JsonValue::new_array();
+			for name in names {
+				let contents = fs::read_to_string(format!("{}/{}.json", dict_path, name)).unwrap_or_default();
+				let count = jzon::parse(&contents).map_or(0, |parsed| parsed[key].len());
+				list.push(jzon::object!{
+					name: name.as_str(),
+					[count_name]: count,
+				}).unwrap();
 			}
+			println!("{}", list.dump());
 		} else {
-			for word in parsed["words"].as_array().unwrap() {
-				dict.push(DictEntry::Word(word.as_str().unwrap().to_string()));
+			println!("{}", names.join("\n"));
+		}
+		return;
+	}
+	
+	let term_color_support = get_term_color_support();
+	settings.inverse_caret = matches!(term_color_support, TermColorSupport::None);
+	
+	let mut lang = match matches.opt_str("l") {
+		Some(l) => l,
+		None if matches.opt_present("menu") => {
+			match menu::pick("Choose a language", &list_dicts(&dict_path), &theme, &term_color_support) {
+				Some(l) => l,
+				None => return
 			}
+		},
+		None => "english".to_string()
+	};
+	
+	let quotes = game_mode == GameMode::Quote;
+	let author = matches.opt_str("a");
+	// Languages picked later get their defaults over the options as given on the command line
+	let base_options = (word_options.clone(), settings);
+	// Numbers are made up on the fly, there is no list to load for them
+	let mut dict = if word_options.numbers_only.is_some() {
+		Vec::new()
+	} else {
+		let parsed = downloaded.unwrap_or_else(|| read_dict(&dict_path, &lang, quotes));
+		let offending = invalid_words(&parsed);
+		if !offending.is_empty() {
+			eprintln!(
+				"Removed invalid characters from {} words: {}{}",
+				offending.len(),
+				offending.iter().take(10).cloned().collect::<Vec<String>>().join(", "),
+				if offending.len() > 10 { ", ..." } else { "" }
+			);
 		}
-		dict
+		apply_defaults(&parsed, quotes, &mut word_options, &mut settings, |option| matches.opt_present(option));
+		parse_dict(&parsed, quotes, author.as_deref(), &word_options).unwrap_or_else(|e| panic!("{}", e))
 	};
+	if let GameMode::ListWords{percent} = game_mode {
+		if list_word_count(percent, &dict) == 0 {
+			panic!("There are too few words in the list for {}.", game_mode);
+		}
+	}
+	
+	// Every text is picked from the same generator, so a seed gives the same sequence of tests
+	// ChaCha8 gives the same numbers on every platform and rand version, unlike StdRng
+	let mut rng = match matches.opt_str("seed") {
+		Some(seed) => ChaCha8Rng::seed_from_u64(seed.parse().unwrap_or_else(|_| panic!("{} is not a valid seed.", seed))),
+		None => ChaCha8Rng::from_rng(&mut rand::rng())
+	};
+	
+	if matches.opt_present("dump-text") {
+		println!("{}", generate_text(&game_mode, &dict, &word_options, &mut rng).0.trim_end());
+		return;
+	}
 	
-	let term_color_support = get_term_color_support();
-	let mut rng = rand::rng();
 	let mut stdout = stdout();
 
-	terminal::enable_raw_mode().unwrap();
-	execute!(stdout, cursor::Hide).unwrap();
-	execute!(stdout, terminal::DisableLineWrap).unwrap();
-	execute!(stdout, terminal::EnterAlternateScreen).unwrap();
-	
-	// This will be called on a panic so the terminal doesn't stay all messed up
-	panic::set_hook(Box::new(|info| {
-		let mut stdout = std::io::stdout();
-		
-		terminal::disable_raw_mode().unwrap();
-		execute!(stdout, terminal::EnableLineWrap).unwrap();
-		execute!(stdout, terminal::LeaveAlternateScreen).unwrap();
-		execute!(stdout, cursor::Show).unwrap();
-		
-		println!("{}", std::backtrace::Backtrace::force_capture());
-		println!("{}", info);
-	}));
+	let set_title = matches.opt_present("set-title");
+	enter_tui(set_title);
+	let death = matches.opt_present("death");
+	let must_correct = matches.opt_present("must-correct");
+	let estimate_wpm = settings.pace.filter(|&wpm| wpm > 0.0).unwrap_or(ESTIMATE_WPM); // Speed the length of the tests is estimated at
 	
 	let mut cpt_it = 0;
+	let mut quote_deck = QuoteDeck::default();
+	let mut same_text: Option<(String, Vec<String>)> = None; // Text and sources to type again, after Tab
+	let mut show_stats = false; // Live statistics overlay, toggled with Ctrl+S
+	let mut show_width = matches.opt_present("show-width"); // Text width ruler, toggled with Ctrl+W
+	let mut focused = true; // Whether the terminal has focus, the screen is dimmed when it doesn't
+	let mut stage_results: Vec<Results> = Vec::new();
+	let mut key_stats = KeyStats::new(matches.opt_present("split-case"));
+	let mut last_results: Option<(String, Results)> = None; // Mode and results of the last finished run, for --export and --json
+	let mut stopped_early = false; // Whether the last results are those of a test quit with Ctrl+C
+	let history_path = matches.opt_str("history");
+	// Key speeds of the past runs, which --adaptive picks the words from, with those of this session added as they come
+	let adaptive = matches.opt_present("adaptive");
+	let mut adaptive_keys = KeyStats::new(false);
+	if let (true, Some(path)) = (adaptive, &history_path) {
+		for record in results::read_history(path) {
+			adaptive_keys.add_json(&record["keys"]);
+		}
+	}
+	let mut event_stream = matches.opt_str("event-stream").map(|target| EventStream::open(&target));
+	let mut language = if matches!(game_mode, GameMode::Text{..}) { None } else { Some(matches.opt_str("url").unwrap_or(lang.clone())) };
 	
 	'main: loop {
-		let source = String::new();
+		if let Some(log) = &replay_log {
+			game::replay(log, &theme, &game::Settings { strict_space: log.strict_space, skip_indent: log.skip_indent, ..settings }, &term_color_support);
+			break 'main;
+		}
 		
-		let text = match game_mode {
-			GameMode::CountedWords{number_of_words} => {
-				let mut text = String::new();
-				for _ in 0..number_of_words {
-					match &dict[rng.random_range(0..dict.len())] {
-						DictEntry::Word(w) => {
-							text.push_str(w);
-							text.push_str(" ");
+		if matches!(game_mode, GameMode::Text{..}) && cpt_it > 0 && same_text.is_none() {
+			break 'main;
+		}
+		let stage_mode = stages.get(stage_results.len()).map(|&n| GameMode::CountedWords{number_of_words: n});
+		if adaptive {
+			word_options.weights = adaptive_weights(&dict, &adaptive_keys.slowness());
+		}
+		let (mut text, mut sources) = same_text.take().unwrap_or_else(|| if quote_chain && game_mode == GameMode::Quote {
+			let (text, source) = quote_deck.draw(&dict, &mut rng);
+			(text, vec![source])
+		} else {
+			generate_text(stage_mode.as_ref().unwrap_or(&game_mode), &dict, &word_options, &mut rng)
+		});
+		
+		let mut keylog = KeyLog::new(&text, settings.strict_space, settings.skip_indent);
+		// Words typed before the test to get going, neither timed nor scored
+		let mut warmup = (warmup_words > 0 && matches!(stage_mode.as_ref().unwrap_or(&game_mode), GameMode::CountedWords{..} | GameMode::TimedWords{..} | GameMode::ListWords{..}))
+			.then(|| random_words(warmup_words, &dict, &word_options, &mut rng));
+		if let Some(words) = &warmup {
+			text = format!("{}{}", words, text);
+		}
+		
+		let mut typed = String::new();
+		let mut cursor_pos = 0;
+		if settings.skip_indent {
+			game::skip_indent(&mut typed, &mut cursor_pos, &text, false);
+		}
+		let mut undo = game::UndoStack::new();
+		let mut start = Instant::now();
+		let mut stats = Stats::new(idle_timeout);
+		let mut title_second = None; // Second of the test the window title was last updated at
+		let mut scroll = game::Scroll::default();
+		let mut rejected: Option<Instant> = None; // Time a space was refused by --must-correct, the text flashes for a moment
+		let mut peeked: Option<Instant> = None; // Last time Ctrl+P was pressed, the whole text shows while it's held
+		// Time since the first key, shown in the corner of the tests without a set duration
+		let clock = !matches!(stage_mode.as_ref().unwrap_or(&game_mode), GameMode::TimedWords{..});
+		let mut last_frame: Option<Pixels> = None; // Frame on screen, the results screen and flashes draw over it between tests
+		let mut died = None; // Word and character of the text reached when --death ended the test
+		key_stats.start_run();
+		adaptive_keys.start_run();
+		let mut run_keys = KeyStats::new(false); // Key speeds of this run alone, kept in the history
+		
+		'game: loop {
+			if died.is_none() && quote_chain && game::is_finished(&typed, &text, &settings) {
+				// Keep going with another quote, after a blank line
+				let (next, source) = quote_deck.draw(&dict, &mut rng);
+				text = format!("{}\n\n{}", text.trim_end(), next);
+				keylog.text = text.clone();
+				sources.push(source);
+			} else if died.is_some() || game::is_finished(&typed, &text, &settings) {
+				if let Some(path) = &record_path {
+					keylog.save(path);
+				}
+				
+				let results = stats.results(game::correct_chars(&typed, &text, &settings));
+				let wpms = wpm_over_time(&stats.samples, results.time);
+				let words = game::word_marks(&typed, &text, &settings, &theme);
+				last_results = Some((stage_mode.as_ref().unwrap_or(&game_mode).to_string(), results));
+				// The best is looked up before this run joins the history
+				let best = history_path.as_ref().and_then(|path| results::personal_best(&results::read_history(path), &last_results.as_ref().unwrap().0, language.as_deref()));
+				if let Some(path) = &history_path {
+					let mut record = results::json(&last_results.as_ref().unwrap().0, language.as_deref(), &results, true, &format_date(SystemTime::now()));
+					record["keys"] = run_keys.to_json();
+					results::append_history(path, &record);
+				}
+				let title = if let Some((word, chars)) = died {
+					format!("died at word {} of {}, after {} correct characters", word, text.split_whitespace().count(), chars)
+				} else if stages.is_empty() {
+					"results".to_string()
+				} else {
+					format!("stage {}/{}", stage_results.len() + 1, stages.len())
+				};
+				// The next tests can switch between words, time and quotes from the results screen
+				// and the language, picked from a menu
+				let switchable = stages.is_empty() && !matches!(game_mode, GameMode::Text{..}) && !matches.opt_present("url");
+				let switchable_language = word_options.numbers_only.is_none();
+				let mut notice: Option<String> = None; // Replaces the title after a language that couldn't be loaded
+				loop {
+					let has_quotes = fs::metadata(format!("{}/{}/{}.json", data_dir, QUOTES_PATH, lang)).is_ok();
+					let options = switchable.then(|| mode_options(&game_mode, switchable_language.then_some(lang.as_str()), has_quotes, &theme));
+					match results::show(notice.as_deref().unwrap_or(&title), &results, &wpms, &words, best, options.as_ref(), &theme, &term_color_support) {
+						results::Choice::Continue => break,
+						results::Choice::Quit => break 'main,
+						results::Choice::Key('l') if switchable_language => {
+							let quotes = game_mode == GameMode::Quote;
+							let dir = format!("{}/{}", data_dir, if quotes { QUOTES_PATH } else { LANGUAGES_PATH });
+							if let Some(name) = menu::pick("Choose a language", &list_dicts(&dir), &theme, &term_color_support) {
+								match switch_dict(&dir, &name, quotes, author.as_deref(), (&base_options.0, &base_options.1), |option| matches.opt_present(option)) {
+									Ok((new_dict, ..)) if matches!(game_mode, GameMode::ListWords{percent} if list_word_count(percent, &new_dict) == 0) => {
+										notice = Some(format!("still using {}: there are too few words in {} for {}", lang, name, game_mode));
+									},
+									Ok((new_dict, new_options, new_settings)) => {
+										dict = new_dict;
+										word_options = new_options;
+										settings = new_settings;
+										lang = name;
+										language = Some(lang.clone());
+										notice = None;
+									},
+									Err(e) => notice = Some(format!("still using {}: {}", lang, e))
+								}
+							}
 						},
-						_ => {}
+						results::Choice::Key(c) => if let Some(mode) = switch_mode(&game_mode, c).filter(|m| has_quotes || *m != GameMode::Quote) {
+							// Like a language from the menu, a list that can't be loaded leaves the mode as it was
+							let quotes = mode == GameMode::Quote;
+							if quotes != (game_mode == GameMode::Quote) {
+								let dir = format!("{}/{}", data_dir, if quotes { QUOTES_PATH } else { LANGUAGES_PATH });
+								match switch_dict(&dir, &lang, quotes, author.as_deref(), (&base_options.0, &base_options.1), |option| matches.opt_present(option)) {
+									Ok((new_dict, new_options, new_settings)) => {
+										dict = new_dict;
+										word_options = new_options;
+										settings = new_settings;
+									},
+									Err(e) => {
+										notice = Some(format!("still using {}: {}", game_mode, e));
+										continue;
+									}
+								}
+							}
+							notice = None;
+							game_mode = mode;
+						}
 					}
 				}
-				text
-			},
-			GameMode::TimedWords{time} => {
-				let mut text = String::new();
-				for _ in 0..100 {
-					match &dict[rng.random_range(0..dict.len())] {
-						DictEntry::Word(w) => {
-							text.push_str(w);
-							text.push_str(" ");
-						},
-						_ => {}
+				
+				if !stages.is_empty() {
+					stage_results.push(results);
+					if stage_results.len() == stages.len() {
+						let words: Vec<String> = stages.iter().map(|n| n.to_string()).collect();
+						last_results = Some((format!("{} words", words.join(",")), Results::total(&stage_results)));
+						results::show(&format!("total of {} stages", stages.len()), &Results::total(&stage_results), &[], &ColoredText::new(), None, None, &theme, &term_color_support);
+						break 'main;
 					}
 				}
-				text
-			},
-			GameMode::Quote => {
-				let mut text = String::new();
-				match &dict[rng.random_range(0..dict.len())] {
-					DictEntry::Quote{text: t, source: s} => {
-						text.push_str(t);
-						let source = String::from_str(s).unwrap();
-					},
-					_ => {}
+				break 'game;
+			}
+			
+			// A peek lays out the whole text as wrapped lines, dimmed away from the caret
+			let peeking = peeked.is_some_and(|t| t.elapsed() < PEEK_HOLD);
+			let peek_settings;
+			let frame_settings = if peeking {
+				peek_settings = game::Settings { single_line: false, fixed_caret: false, smooth_scroll: false, mini: false, lines: None, show_context: true, ..settings };
+				&peek_settings
+			} else {
+				&settings
+			};
+			let mut pixels = game::frame(&typed, &text, cursor_pos, stats.elapsed(), &theme, frame_settings, &mut scroll);
+			if settings.mini {
+				// The speed alone on the right of the line, the rest would take rows a small pane doesn't have
+				if stats.started.is_some() {
+					let wpm = stats.results(game::correct_chars(&typed, &text, &settings)).wpm();
+					pixels.print(&format!("{:.0}wpm", wpm), theme.untyped, false, false, (pixels.size.0, 0), HCentering::Right, VCentering::Top);
 				}
-				text
-			},
-			GameMode::Text{ref text} => {
-				if cpt_it > 0 {
-					break 'main;
+			} else {
+				if show_stats {
+					let results = stats.results(game::correct_chars(&typed, &text, &settings));
+					let overlay = format!(
+						"{:.0} wpm  {:.0}% acc  {}s",
+						results.wpm(),
+						results.shown_accuracy(),
+						results.time.as_secs()
+					);
+					pixels.print(&overlay, theme.untyped, false, false, (1, 0), HCentering::Left, VCentering::Top);
+				}
+				if show_width {
+					game::draw_ruler(&mut pixels, &theme);
+				}
+				if stats.started.is_none() && typed.is_empty() {
+					// Timed tests last as long as they last, the others get an idea of how long they'll take
+					if !matches!(stage_mode.as_ref().unwrap_or(&game_mode), GameMode::TimedWords{..}) {
+						let estimate = format!("≈ {:.0}s at {:.0}wpm", estimated_time(&text, estimate_wpm).as_secs_f32(), estimate_wpm);
+						pixels.print(&estimate, theme.untyped, false, false, (pixels.size.0 / 2, 0), HCentering::Center, VCentering::Top);
+					}
+					// Faint instructions under the text until the first key, for whoever opens ttype for the first time
+					let faint = theme.untyped.blend(theme.background.unwrap_or(Color::new_rgb(0, 0, 0)), 0.5);
+					for (i, hint) in ["start typing…", "[ctrl+r] new text  [tab] same text  [ctrl+c] quit"].iter().enumerate() {
+						pixels.print(hint, faint, false, false, (pixels.size.0 / 2, pixels.size.1 * 3 / 4 + i), HCentering::Center, VCentering::Top);
+					}
+				}
+				if let (true, Some(elapsed)) = (clock, stats.elapsed()) {
+					let clock_text = format!("{}:{:02}", elapsed.as_secs() / 60, elapsed.as_secs() % 60);
+					pixels.print(&clock_text, theme.untyped, false, false, (pixels.size.0 - 2, pixels.size.1 - 1), HCentering::Right, VCentering::Bottom);
+				}
+				if warmup.is_some() {
+					let faint = theme.untyped.blend(theme.background.unwrap_or(Color::new_rgb(0, 0, 0)), 0.5);
+					pixels.print(&format!("warmup: {} words before the test", warmup_words), faint, false, false, (pixels.size.0 / 2, pixels.size.1 - 1), HCentering::Center, VCentering::Bottom);
+				}
+				if stats.is_idle() {
+					pixels.print("idle", theme.untyped, false, false, (pixels.size.0 - 2, 0), HCentering::Right, VCentering::Top);
+				}
+				if quote_chain {
+					pixels.print(&sources.join(", "), theme.untyped, false, false, (pixels.size.0 / 2, pixels.size.1 - 1), HCentering::Center, VCentering::Bottom);
 				}
-				text.clone()
 			}
-		};
-		
-		let text_words: Vec<&str> = text.split_whitespace().collect();
-		let mut typed = String::new();
-		let mut cursor_pos = 0;
-		
-		'game: loop {
-			let typed_words: Vec<&str> = typed.split_whitespace().collect();
-			
-			if typed_words.len() > text_words.len()
-			|| (typed_words.len() == text_words.len() && (typed.ends_with(' ')
-			|| typed_words.last().unwrap_or(&"").eq(text_words.last().unwrap_or(&"")))) {
-				break 'game;
+			if rejected.is_some_and(|t| t.elapsed() < REJECT_FLASH) {
+				let mut tint = pixels.clone();
+				tint.color_all(theme.wrong);
+				pixels.comp_blend(&tint, (pixels.size.0 as i32 / 2, pixels.size.1 as i32 / 2), 0.4);
 			}
-			
-			let tsize = terminal::size().unwrap();
-			let mut pixels = Pixels::new((tsize.0 as usize, tsize.1 as usize));
-			
-			let mut to_print = correct_combine(&typed, &text);
-			show_cursor(&mut to_print, &typed, &text, cursor_pos);
-			
-			let text_width = used_text_width(tsize.0 as usize);
-			
-			//TODO: Handle newlines
-			to_print.word_wrap(text_width);
-			
-			let start_pos = {
-				if to_print.text().matches('\n').count() > 0 {
-					(tsize.0 as usize - text_width) / 2
-				} else {	
-					(tsize.0 as usize - to_print.text().len()) / 2
+			if !focused {
+				let mut shade = pixels.clone();
+				shade.color_all(theme.background.unwrap_or(Color::new_rgb(0, 0, 0)));
+				pixels.comp_blend(&shade, (pixels.size.0 as i32 / 2, pixels.size.1 as i32 / 2), 0.6);
+			}
+			// Frames like the one on screen, after a key that changed nothing for instance, aren't written again
+			if last_frame.as_ref() != Some(&pixels) {
+				pixels.render(&term_color_support);
+				last_frame = Some(pixels.clone());
+			}
+			let rendered = Instant::now();
+			if let (true, Some(elapsed)) = (set_title, stats.elapsed()) {
+				if title_second != Some(elapsed.as_secs()) {
+					title_second = Some(elapsed.as_secs());
+					let wpm = stats.results(game::correct_chars(&typed, &text, &settings)).wpm();
+					execute!(stdout, terminal::SetTitle(format!("ttype {:.0}wpm", wpm))).unwrap();
 				}
-			};
-			
-			pixels.print_color(
-				&to_print,
-				(start_pos, tsize.1 as usize / 2),
-				HCentering::Left, // We don't center on the middle as that could cause some jitters
-				VCentering::Middle
-			);
+			}
 			
-			pixels.render(&term_color_support);
+			// Redraw at a steady rate while something moves on its own, and when the player becomes idle
+			let animated = (stats.started.is_some() && (settings.pace.is_some() || show_stats || settings.mini) && !stats.is_idle() && !stats.is_paused()) || scroll.is_moving();
+			let next_frame = match (animated, (set_title || clock) && stats.started.is_some() && !stats.is_idle() && !stats.is_paused()) {
+				(true, _) => Some(frame_duration),
+				// The title and the clock are updated every second of the test
+				(false, true) => {
+					let next_second = Duration::from_secs(1) - Duration::from_nanos(stats.elapsed().unwrap_or_default().subsec_nanos() as u64);
+					Some(stats.until_idle().map_or(next_second, |idle| idle.min(next_second)))
+				},
+				(false, false) => stats.until_idle()
+			};
+			// Wake up to end the flash of a refused space or a peek
+			let flash_left = [rejected.map(|t| REJECT_FLASH.saturating_sub(t.elapsed())), peeked.map(|t| PEEK_HOLD.saturating_sub(t.elapsed()))];
+			let next_frame = match flash_left.into_iter().flatten().filter(|left| !left.is_zero()).min() {
+				Some(left) => Some(next_frame.map_or(left, |wait| wait.min(left))),
+				None => next_frame
+			};
 			
 			// Get events
-			let mut key_events:Vec<event::KeyEvent> = Vec::new();
+			let mut events:Vec<event::Event> = Vec::new();
 			if event::poll(Duration::from_secs(0)).unwrap() { // Event is available
 				while event::poll(Duration::from_secs(0)).unwrap() {
-					if let event::Event::Key(key_event) = event::read().unwrap() {
-						key_events.push(key_event);
-					}
+					events.push(event::read().unwrap());
+				}
+			} else if let Some(wait) = next_frame { // Wait for an event until the next frame
+				if event::poll(wait).unwrap() {
+					events.push(event::read().unwrap());
 				}
 			} else { // No event available; wait for one
-				if let event::Event::Key(key_event) = event::read().unwrap() {
-					key_events.push(key_event);
+				events.push(event::read().unwrap());
+			}
+			// Keep taking events until the frame is over, so fast typing doesn't redraw more often than --max-fps
+			while !events.is_empty() {
+				let left = frame_duration.saturating_sub(rendered.elapsed());
+				if left.is_zero() || !event::poll(left).unwrap() {
+					break;
 				}
+				events.push(event::read().unwrap());
 			}
 			
-			// Process events
-			for e in key_events {
+			// Process events, pasted text being typed one character at a time
+			let events = events.into_iter().flat_map(|e| match e {
+				event::Event::Paste(text) => game::paste_keys(&text).into_iter().map(event::Event::Key).collect(),
+				e => vec![e]
+			});
+			for e in events {
+				// Keys pressed after the last word belong to the results screen, not to the finished text
+				if died.is_some() || (!quote_chain && game::is_finished(&typed, &text, &settings)) {
+					break;
+				}
+				let e = match e {
+					event::Event::Key(e) if game::is_press(&e) => e,
+					// Switching to another window shouldn't count against the player
+					event::Event::FocusLost => {
+						focused = false;
+						stats.pause();
+						continue;
+					},
+					event::Event::FocusGained => {
+						focused = true;
+						stats.resume();
+						continue;
+					},
+					// Key releases, resizes and the rest need nothing, a resize is picked up by the next frame
+					_ => continue
+				};
+				let e = match &layout {
+					Some(layout) => layout.remap(e),
+					None => e
+				};
 				if e.code == CharCode('r') && e.modifiers == event::KeyModifiers::CONTROL{
+					// Fade the old text out over two frames so the restart doesn't look like a glitch
+					let mut shade = pixels.clone();
+					shade.color_all(theme.background.unwrap_or(Color::new_rgb(0, 0, 0)));
+					for alpha in [0.5, 0.85] {
+						let mut flash = pixels.clone();
+						flash.comp_blend(&shade, (flash.size.0 as i32 / 2, flash.size.1 as i32 / 2), alpha);
+						flash.print("restarting…", theme.untyped, false, false, (flash.size.0 / 2, flash.size.1 / 2), HCentering::Center, VCentering::Middle);
+						flash.render(&term_color_support);
+						thread::sleep(frame_duration);
+					}
+					break 'game;
+				} else if e.code == event::KeyCode::Tab {
+					// The warmup words aren't part of the text, the next test gets new ones
+					let scored_text = warmup.as_ref().map_or(text.as_str(), |words| &text[words.len()..]);
+					same_text = Some((scored_text.to_string(), sources.clone()));
 					break 'game;
 				} else if e.code == CharCode('c') && e.modifiers == event::KeyModifiers::CONTROL{
+					// Keep what was typed so far instead of throwing the test away
+					if stats.started.is_some() {
+						let results = stats.results(game::correct_chars(&typed, &text, &settings));
+						last_results = Some((stage_mode.as_ref().unwrap_or(&game_mode).to_string(), results));
+						stopped_early = true;
+						if let Some(path) = &history_path {
+							let mut record = results::json(&last_results.as_ref().unwrap().0, language.as_deref(), &results, false, &format_date(SystemTime::now()));
+							record["keys"] = run_keys.to_json();
+							results::append_history(path, &record);
+						}
+					}
 					break 'main;
-				} else if e.code == KeyCode::Backspace {
-					if cursor_pos > 0 {
-						typed.remove(
-							nth_char_idx(&typed, cursor_pos - 1)
-						);
-						cursor_pos -= 1;
+				} else if e.code == CharCode('s') && e.modifiers == event::KeyModifiers::CONTROL {
+					show_stats = !show_stats;
+				} else if e.code == CharCode('w') && e.modifiers == event::KeyModifiers::CONTROL {
+					show_width = !show_width;
+				} else if e.code == CharCode('p') && e.modifiers == event::KeyModifiers::CONTROL {
+					// Terminals repeat a held key, each repeat keeps the peek going
+					peeked = Some(Instant::now());
+				} else if must_correct && matches!(e.code, CharCode(' ' | '\n' | '\r')) && !game::is_shortcut(&e)
+					&& !game::word_correct(&typed, cursor_pos, &text, &settings)
+					&& !game::is_finished(&format!("{} ", typed), &text, &settings) { // The space ending the test always goes through
+					rejected = Some(Instant::now());
+				} else if let Some(words) = &warmup {
+					game::edit(&e, &mut typed, &mut cursor_pos, &mut undo, &settings);
+					// The test starts right after the last warmup word, with the text that follows it
+					let done = if settings.strict_space {
+						typed.chars().count() >= words.chars().count()
+					} else {
+						typed.ends_with(' ') && typed.split_whitespace().count() >= warmup_words
+					};
+					if done {
+						text = text[words.len()..].to_string();
+						warmup = None;
+						typed.clear();
+						cursor_pos = 0;
+						undo.clear();
+						start = Instant::now();
 					}
-				} else if e.code == KeyCode::Delete {
-					if cursor_pos < typed.chars().count() {
-						typed.remove(
-							nth_char_idx(&typed, cursor_pos)
-						);
+				} else {
+					let length = typed.chars().count();
+					let correct = game::correct_chars(&typed, &text, &settings);
+					let expected = game::expected_char(&typed, cursor_pos, &text, &settings);
+					let position = cursor_pos;
+					keylog.push(start.elapsed(), e);
+					game::edit(&e, &mut typed, &mut cursor_pos, &mut undo, &settings);
+					let inserted = typed.chars().count() > length;
+					if settings.skip_indent {
+						let deleted = typed.chars().count() < length;
+						game::skip_indent(&mut typed, &mut cursor_pos, &text, deleted);
 					}
-				} else if e.code == KeyCode::Left {
-					if cursor_pos > 0 {
-						cursor_pos -= 1;
+					stats.record(inserted, correct, game::correct_chars(&typed, &text, &settings));
+					if let (true, Some(expected), Some(c)) = (inserted, expected, typed.chars().nth(position)) {
+						key_stats.record(expected, c);
+						run_keys.record(expected, c);
+						adaptive_keys.record(expected, c);
 					}
-				} else if e.code == KeyCode::Right {
-					if cursor_pos < typed.chars().count() {
-						cursor_pos += 1;
+					if let Some(stream) = &mut event_stream {
+						stream.send(start.elapsed(), e, expected, if inserted { typed.chars().nth(position) } else { None });
 					}
-				} else if let CharCode(c) = e.code {
-					let chars: Vec<char> = typed.chars().collect();
-					
-					if e.code != CharCode(' ')
-						|| (cursor_pos >= chars.len() && chars.last().unwrap_or(&' ').to_owned() != ' ')
-						|| (cursor_pos < chars.len() && chars[cursor_pos - 1] != ' ' && chars[cursor_pos] != ' ') {
-						
-						if cursor_pos >= chars.len() {
-							typed.push(c);
-						} else {
-							typed.insert(
-								nth_char_idx(&typed, cursor_pos),
-								c,
-							);
-						}
-						cursor_pos += 1;
+					// Only a new character can be a mistake, corrections never end the test
+					if death && inserted && game::has_mistake(&typed, &text, &settings) {
+						died = Some((typed.split_whitespace().count().max(1), correct));
 					}
 				}
 			}
@@ -377,8 +1265,102 @@ fn main() {
 		}
 	}
 	
-	terminal::disable_raw_mode().unwrap();
-	execute!(stdout, terminal::EnableLineWrap).unwrap();
-	execute!(stdout, terminal::LeaveAlternateScreen).unwrap();
-	execute!(stdout, cursor::Show).unwrap();
-}
\ No newline at end of file
+	leave_tui();
+	
+	if matches.opt_present("key-stats") {
+		print!("{}", key_stats.report());
+	}
+	
+	if let Some((mode, results)) = &last_results {
+		let date = format_date(SystemTime::now());
+		if matches.opt_present("json") {
+			println!("{}", results::json(mode, language.as_deref(), results, !stopped_early, &date).dump());
+		} else if stopped_early {
+			println!("Stopped before the end: {:.0} wpm, {:.0}% accuracy in {:.1}s", results.wpm(), results.shown_accuracy(), results.time.as_secs_f32());
+		}
+		if let Some(path) = matches.opt_str("export") {
+			let card = results::card(mode, language.as_deref(), results, &date);
+			fs::write(&path, card).unwrap_or_else(|_| panic!("Can't write the results to {}.", path));
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn duplicate_words() {
+		let parsed = jzon::parse(r#"{"words": ["the", "cat", "the", " cat", "sat"]}"#).unwrap();
+		let dict = parse_dict(&parsed, false, None, &WordOptions::default()).unwrap();
+		assert_eq!(dict.len(), 3);
+		
+		let mut rng = ChaCha8Rng::seed_from_u64(0);
+		let text = list_words(100, &dict, &WordOptions::default(), &mut rng);
+		let mut words: Vec<&str> = text.split_whitespace().collect();
+		words.sort();
+		assert_eq!(words, vec!["cat", "sat", "the"]);
+	}
+	
+	#[test]
+	fn quotes_without_repeats() {
+		let dict: Vec<DictEntry> = (0..5).map(|i| DictEntry::Quote{text: i.to_string(), source: String::new()}).collect();
+		let mut deck = QuoteDeck::default();
+		let mut rng = ChaCha8Rng::seed_from_u64(0);
+		for _ in 0..2 {
+			let mut drawn: Vec<String> = (0..5).map(|_| deck.draw(&dict, &mut rng).0).collect();
+			drawn.sort();
+			assert_eq!(drawn, vec!["0", "1", "2", "3", "4"]);
+		}
+	}
+	
+	#[test]
+	fn adaptive_odds() {
+		let dict = vec![DictEntry::Word("ab".to_string()), DictEntry::Word("zz".to_string()), DictEntry::Word("Az".to_string())];
+		assert!(adaptive_weights(&dict, &HashMap::new()).is_none());
+		
+		let slowness = HashMap::from([('a', 0.5), ('z', 2.0)]);
+		let weights = adaptive_weights(&dict, &slowness).unwrap();
+		let mut rng = ChaCha8Rng::seed_from_u64(0);
+		let mut picks = [0; 3];
+		for _ in 0..1000 {
+			picks[weights.sample(&mut rng)] += 1;
+		}
+		assert!(picks[1] > picks[2] && picks[2] > picks[0]);
+	}
+	
+	#[test]
+	fn bigram_words() {
+		let dict = vec![DictEntry::Word("cat".to_string())];
+		let options = WordOptions { bigrams: vec!["th".to_string(), "he".to_string()], ..WordOptions::default() };
+		let mut rng = ChaCha8Rng::seed_from_u64(0);
+		let text = random_words(200, &dict, &options, &mut rng);
+		let words: Vec<&str> = text.split_whitespace().collect();
+		assert!(words.contains(&"cat"));
+		for word in words.iter().filter(|&&w| w != "cat") {
+			assert!((4..=6).contains(&word.len()));
+			assert!(word.as_bytes().chunks(2).all(|pair| pair == b"th" || pair == b"he"));
+		}
+	}
+	
+	#[test]
+	fn seeded_words() {
+		// A seed shared with friends has to give them the same words, whatever they run it on
+		let dict: Vec<DictEntry> = ["a", "b", "c", "d"].iter().map(|w| DictEntry::Word(w.to_string())).collect();
+		let mut rng = ChaCha8Rng::seed_from_u64(42);
+		assert_eq!(random_words(8, &dict, &WordOptions::default(), &mut rng), "a c a d d b b c ");
+	}
+	
+	#[test]
+	fn unusable_lists() {
+		let words = jzon::parse(r#"{"words": ["a", "be"]}"#).unwrap();
+		let long_words = WordOptions { min_length: 6, ..WordOptions::default() };
+		assert!(parse_dict(&words, false, None, &long_words).is_err());
+		assert!(parse_dict(&jzon::parse(r#"{"words": [1]}"#).unwrap(), false, None, &WordOptions::default()).is_err());
+		
+		let quotes = jzon::parse(r#"{"quotes": [{"text": "hi", "source": "Ann"}]}"#).unwrap();
+		assert_eq!(parse_dict(&quotes, true, Some("ann"), &WordOptions::default()).unwrap().len(), 1);
+		assert!(parse_dict(&quotes, true, Some("Bob"), &WordOptions::default()).is_err());
+		assert!(parse_dict(&jzon::parse(r#"{"quotes": [{"text": "hi"}]}"#).unwrap(), true, None, &WordOptions::default()).is_err());
+	}
+}
@@ -0,0 +1,58 @@
+use crossterm::{event::{self, KeyCode, KeyModifiers}, terminal};
+
+use crate::color::TermColorSupport;
+use crate::game::key_press;
+use crate::pixels::*;
+use crate::theme::Theme;
+use crate::tui::{enter_tui, is_active, leave_tui};
+
+// Let the player pick one of the items with the arrow keys and Enter
+// Returns None if the menu was closed with Esc or Ctrl+C
+// Opened between tests, the terminal is already set up and is left as it was
+pub fn pick(title: &str, items: &[String], theme: &Theme, term_color_support: &TermColorSupport) -> Option<String> {
+	let standalone = !is_active();
+	if standalone {
+		enter_tui(false);
+	}
+	
+	let mut selected: usize = 0;
+	let picked = loop {
+		let tsize = terminal::size().unwrap();
+		let mut pixels = Pixels::new((tsize.0 as usize, tsize.1 as usize));
+		pixels.background_all(theme.background);
+		
+		// Keep the selected item in the middle of the list, unless it's close to either end
+		let rows = (tsize.1 as usize).saturating_sub(4).max(1);
+		let first = selected.saturating_sub(rows / 2).min(items.len().saturating_sub(rows));
+		
+		let center = tsize.0 as usize / 2;
+		pixels.print(title, theme.typed, false, true, (center, 1), HCentering::Center, VCentering::Top);
+		for (row, item) in items.iter().enumerate().skip(first).take(rows) {
+			let line = if row == selected { format!("> {} <", item) } else { item.to_string() };
+			let color = if row == selected { theme.caret } else { theme.untyped };
+			pixels.print(&line, color, false, false, (center, 3 + row - first), HCentering::Center, VCentering::Top);
+		}
+		pixels.render(term_color_support);
+		
+		if let Some(e) = key_press(event::read().unwrap()) {
+			match e.code {
+				KeyCode::Up => selected = selected.saturating_sub(1),
+				KeyCode::Down => selected = (selected + 1).min(items.len().saturating_sub(1)),
+				KeyCode::PageUp => selected = selected.saturating_sub(rows),
+				KeyCode::PageDown => selected = (selected + rows).min(items.len().saturating_sub(1)),
+				KeyCode::Home => selected = 0,
+				KeyCode::End => selected = items.len().saturating_sub(1),
+				KeyCode::Enter => break items.get(selected).cloned(),
+				KeyCode::Esc => break None,
+				KeyCode::Char('c') if e.modifiers == KeyModifiers::CONTROL => break None,
+				_ => {}
+			}
+		}
+	};
+	
+	if standalone {
+		leave_tui();
+	}
+	
+	picked
+}
@@ -1,3 +1,7 @@
+use std::io::{stdout, BufWriter, Write};
+
+use crossterm::{cursor, Command};
+
 use crate::color::*;
 
 pub enum HCentering {
@@ -12,10 +16,12 @@ pub enum VCentering {
 	Bottom
 }
 
+#[derive(Clone, PartialEq)]
 pub struct Pixels {
 	pub size: (usize, usize),
 	chars: Vec<char>,
 	colors: Vec<Color>,
+	backgrounds: Vec<Option<Color>>, // None keeps the terminal's background
 	escapes: Vec<Vec<usize>>, // Custom escape sequences
 }
 
@@ -25,6 +31,7 @@ impl Pixels {
 			size,
 			chars: vec![' '; size.0 * size.1],
 			colors: vec![Color::new_rgb(255, 255, 255); size.0 * size.1],
+			backgrounds: vec![None; size.0 * size.1],
 			escapes: vec![Vec::new(); size.0 * size.1],
 		}
 	}
@@ -33,6 +40,7 @@ impl Pixels {
 	pub fn clear(&mut self) {
 		self.chars = vec![' '; self.size.0 * self.size.1];
 		self.colors = vec![Color::new_rgb(255, 255, 255); self.size.0 * self.size.1];
+		self.backgrounds = vec![None; self.size.0 * self.size.1];
 		self.escapes = vec![Vec::new(); self.size.0 * self.size.1];
 	}
 	
@@ -46,6 +54,11 @@ impl Pixels {
 		self.colors[position.1 * self.size.0 + position.0] = color;
 	}
 	
+	// Set the background color of the pixel at the given position
+	pub fn set_background(&mut self, position: (usize, usize), color: Option<Color>) {
+		self.backgrounds[position.1 * self.size.0 + position.0] = color;
+	}
+	
 	// Set custom escape sequences for the pixel at the given position
 	pub fn set_escapes(&mut self, position: (usize, usize), escapes: Vec<usize>) {
 		self.escapes[position.1 * self.size.0 + position.0] = escapes;
@@ -66,6 +79,15 @@ impl Pixels {
 		self.add_escape(position, 1);
 	}
 	
+	// Show the underlined pixels in inverse video too, for terminals where an underline is hard to see
+	pub fn inverse_underlined(&mut self) {
+		for escapes in self.escapes.iter_mut() {
+			if escapes.contains(&4) && !escapes.contains(&7) {
+				escapes.push(7);
+			}
+		}
+	}
+	
 	// Get the color of a pixel at the given position
 	pub fn get_pixel(&self, position: (usize, usize)) -> Option<(char, Color)> {
 		if position.0 < self.size.0 && position.1 < self.size.1 {
@@ -84,7 +106,13 @@ impl Pixels {
 		}
 	}
 	
+	// Set the background color of all the pixels
+	pub fn background_all(&mut self, color: Option<Color>) {
+		self.backgrounds = vec![color; self.size.0 * self.size.1];
+	}
+	
 	// Print a string to the pixels in the given color
+	#[allow(clippy::too_many_arguments)]
 	pub fn print(&mut self, text: &str, color: Color, underline: bool, bold: bool, position: (usize, usize), hc: HCentering, vc: VCentering) {
 		let colored_text = ColoredText::new_text(text, color, underline, bold);
 		self.print_color(&colored_text, position, hc, vc)
@@ -96,20 +124,26 @@ impl Pixels {
 		
 		let mut shift = match hc {
 			HCentering::Left => 0,
-			HCentering::Center => - (text.split('\n').iter().max_by_key(|s| s.len()).map_or(0, |s| s.len()) as i32 / 2),
-			HCentering::Right => - (text.split('\n').iter().max_by_key(|s| s.len()).map_or(0, |s| s.len()) as i32)
+			HCentering::Center => - (text.split('\n').iter().map(|s| s.chars().count()).max().unwrap_or(0) as i32 / 2),
+			HCentering::Right => - (text.split('\n').iter().map(|s| s.chars().count()).max().unwrap_or(0) as i32)
 		};
 		
 		let mut y = match vc {
 			VCentering::Top => position.1 as i32,
-			VCentering::Middle => position.1 as i32 - {let mut count = 0; for c in text.chars() { if c == '\n' { count += 1; } } count} / 2,
+			// position is the lower of the two middle rows of an even height (height / 2), so an even number of lines
+			// starts one row higher than an odd one to stay centered, e.g. 2 lines over rows 11 and 12 of 24
+			VCentering::Middle => position.1 as i32 - ({let mut count = 0; for c in text.chars() { if c == '\n' { count += 1; } } count} + 1) / 2,
 			VCentering::Bottom => position.1 as i32 - {let mut count = 0; for c in text.chars() { if c == '\n' { count += 1; } } count}
 		};
 		
+		let colors = text.colors();
+		let underline = text.underline();
+		let bold = text.bold();
+		let backgrounds = text.backgrounds();
 		let mut current_line_len = 0;
 		
-		for i in 0..text.len() {
-			if ttext[i] == '\n' {
+		for (i, &ch) in ttext.iter().enumerate() {
+			if ch == '\n' {
 				y += 1;
 				shift -= current_line_len + 1;
 				current_line_len = 0;
@@ -117,14 +151,17 @@ impl Pixels {
 				let pos = position.0 as i32 + shift + i as i32;
 				
 				if pos >= 0 && pos < self.size.0 as i32 && y >= 0 && y < self.size.1 as i32{
-					self.set_char((pos as usize, y as usize), ttext[i]);
-					self.set_color((pos as usize, y as usize), text.colors()[i]);
-					if text.underline()[i] {
+					self.set_char((pos as usize, y as usize), ch);
+					self.set_color((pos as usize, y as usize), colors[i]);
+					if underline[i] {
 						self.underline((pos as usize, y as usize));
 					}
-					if text.bold()[i] {
+					if bold[i] {
 						self.bold((pos as usize, y as usize));
 					}
+					if backgrounds[i].is_some() {
+						self.set_background((pos as usize, y as usize), backgrounds[i]);
+					}
 				}
 				
 				current_line_len += 1;
@@ -132,6 +169,88 @@ impl Pixels {
 		}
 	}
 	
+	// Print lines of a ColoredText in columns of rows lines each, flowing from one column to the next
+	// corner is the top left of the first column, the next ones start every column_step cells
+	pub fn print_columns(&mut self, text: &ColoredText, corner: (usize, usize), column_step: usize, rows: usize) {
+		let mut line_starts = vec![0];
+		line_starts.extend(text.chars().iter().enumerate().filter(|(_, &c)| c == '\n').map(|(i, _)| i + 1));
+		let rows = rows.max(1);
+		for (column, first) in (0..line_starts.len()).step_by(rows).enumerate() {
+			// Up to the line return before the first line of the next column
+			let end = line_starts.get(first + rows).map_or(text.len(), |&next| next - 1);
+			self.print_color(&text.slice(line_starts[first], end), (corner.0 + column * column_step, corner.1), HCentering::Left, VCentering::Top);
+		}
+	}
+	
+	// Fill all the pixels with a character in the given color
+	pub fn fill(&mut self, character: char, color: Color) {
+		self.chars = vec![character; self.size.0 * self.size.1];
+		self.colors = vec![color; self.size.0 * self.size.1];
+	}
+	
+	// Fill a rectangle with a character, parts outside the pixels are skipped
+	pub fn fill_rect(&mut self, corner: (i32, i32), size: (usize, usize), character: char, color: Color) {
+		let x_range = corner.0.max(0)..(corner.0 + size.0 as i32).min(self.size.0 as i32);
+		for y in corner.1.max(0)..(corner.1 + size.1 as i32).min(self.size.1 as i32) {
+			for x in x_range.clone() {
+				self.set_char((x as usize, y as usize), character);
+				self.set_color((x as usize, y as usize), color);
+			}
+		}
+	}
+	
+	// Draw the outline of a rectangle with a character, parts outside the pixels are skipped
+	pub fn draw_rect(&mut self, corner: (i32, i32), size: (usize, usize), character: char, color: Color) {
+		if size.0 == 0 || size.1 == 0 {
+			return;
+		}
+		let (right, bottom) = (corner.0 + size.0 as i32 - 1, corner.1 + size.1 as i32 - 1);
+		self.fill_rect(corner, (size.0, 1), character, color);
+		self.fill_rect((corner.0, bottom), (size.0, 1), character, color);
+		self.fill_rect(corner, (1, size.1), character, color);
+		self.fill_rect((right, corner.1), (1, size.1), character, color);
+	}
+	
+	// Draw the outline of a box with box-drawing characters, parts outside the pixels are skipped
+	pub fn draw_box(&mut self, corner: (i32, i32), size: (usize, usize), color: Color) {
+		if size.0 < 2 || size.1 < 2 {
+			return;
+		}
+		let (right, bottom) = (corner.0 + size.0 as i32 - 1, corner.1 + size.1 as i32 - 1);
+		
+		for y in corner.1..=bottom {
+			for x in corner.0..=right {
+				let ch = match (x == corner.0, x == right, y == corner.1, y == bottom) {
+					(true, _, true, _) => '┌',
+					(_, true, true, _) => '┐',
+					(true, _, _, true) => '└',
+					(_, true, _, true) => '┘',
+					(true, _, _, _) | (_, true, _, _) => '│',
+					(_, _, true, _) | (_, _, _, true) => '─',
+					_ => continue
+				};
+				if x >= 0 && x < self.size.0 as i32 && y >= 0 && y < self.size.1 as i32 {
+					self.set_char((x as usize, y as usize), ch);
+					self.set_color((x as usize, y as usize), color);
+				}
+			}
+		}
+	}
+	
+	// Copy of count rows starting at first, clamped to the pixels
+	pub fn crop_rows(&self, first: usize, count: usize) -> Pixels {
+		let first = first.min(self.size.1);
+		let count = count.min(self.size.1 - first);
+		let range = first * self.size.0..(first + count) * self.size.0;
+		Pixels {
+			size: (self.size.0, count),
+			chars: self.chars[range.clone()].to_vec(),
+			colors: self.colors[range.clone()].to_vec(),
+			backgrounds: self.backgrounds[range.clone()].to_vec(),
+			escapes: self.escapes[range].to_vec(),
+		}
+	}
+	
 	// Composite to_comp onto self centered at position (0,0 = top left corner of self)
 	pub fn comp(&mut self, to_comp: &Pixels, position: (i32, i32)) {
 		let origin = (position.0 - to_comp.size.0  as i32 / 2, position.1 - to_comp.size.1 as i32 / 2);
@@ -150,32 +269,143 @@ impl Pixels {
 		}
 	}
 	
-	// Render the pixels line by line
-	pub fn render (&self, term_color_support: &TermColorSupport) {
+	// Same as comp, but the colors of to_comp are blended over the colors of self by alpha (0.0 to 1.0)
+	pub fn comp_blend(&mut self, to_comp: &Pixels, position: (i32, i32), alpha: f32) {
+		let origin = (position.0 - to_comp.size.0  as i32 / 2, position.1 - to_comp.size.1 as i32 / 2);
+		
+		for i in 0..to_comp.size.1 {
+			let y = origin.1 + i as i32;
+			
+			for j in 0..to_comp.size.0 {
+				let x = origin.0 + j as i32;
+				
+				if x >= 0 && x < self.size.0 as i32 && y >= 0 && y < self.size.1 as i32 && to_comp.chars[i * to_comp.size.0 + j] != ' ' {
+					self.set_char((x as usize, y as usize), to_comp.chars[i * to_comp.size.0 + j]);
+					let below = self.colors[y as usize * self.size.0 + x as usize];
+					self.set_color((x as usize, y as usize), below.blend(to_comp.colors[i * to_comp.size.0 + j], alpha));
+				}
+			}
+		}
+	}
+	
+	// Escape sequences drawing the whole pixels, styles are only written where they change
+	pub fn to_ansi(&self, term_color_support: &TermColorSupport) -> String {
+		let mut frame = String::new();
 		for i in 0..self.size.1 {
-			crossterm::execute!(std::io::stdout(), crossterm::cursor::MoveTo(0, i as u16)).unwrap();
-			let mut line = String::new();
+			cursor::MoveTo(0, i as u16).write_ansi(&mut frame).unwrap();
+			
+			// Custom escapes, color and background currently applied by the terminal
+			let mut applied: (&[usize], Option<Color>, Option<Color>) = (&[], None, None);
 			for j in 0..self.size.0 {
-				if self.escapes[i * self.size.0 + j].len() > 0 {
-					line.push_str("\x1b[");
-					for e in &self.escapes[i * self.size.0 + j] {
-						line.push_str(&e.to_string());
-						line.push(';');
-					}
-					line.pop();
-					line.push_str("m");
+				let idx = i * self.size.0 + j;
+				let escapes = self.escapes[idx].as_slice();
+				let color = Some(self.colors[idx]);
+				let background = self.backgrounds[idx];
+				
+				// Attributes and backgrounds can't be turned off one by one, start over from a reset
+				if applied.0.iter().any(|e| !escapes.contains(e)) || (applied.2.is_some() && background.is_none()) {
+					frame.push_str("\x1b[0m");
+					applied = (&[], None, None);
+				}
+				
+				let added: Vec<String> = escapes.iter().filter(|e| !applied.0.contains(e)).map(|e| e.to_string()).collect();
+				if !added.is_empty() {
+					frame.push_str(&format!("\x1b[{}m", added.join(";")));
 				}
 				
-				match self.colors[i * self.size.0 + j].to_escape(&term_color_support) {
-					Some(e) => line.push_str(e.as_str()),
-					None => {}
+				if applied.1 != color {
+					if let Some(e) = self.colors[idx].to_escape(term_color_support) {
+						frame.push_str(e.as_str());
+					}
 				}
 				
-				line.push(self.chars[i * self.size.0 + j] as char);
+				if applied.2 != background {
+					if let Some(e) = background.and_then(|c| c.to_bg_escape(term_color_support)) {
+						frame.push_str(e.as_str());
+					}
+				}
 				
-				line.push_str("\x1b[0m");
+				applied = (escapes, color, background);
+				frame.push(self.chars[idx]);
 			}
-			print!("{}", line);
+			frame.push_str("\x1b[0m");
 		}
+		frame
+	}
+	
+	// Render the pixels line by line
+	pub fn render (&self, term_color_support: &TermColorSupport) {
+		// Build the whole frame first so it's written and flushed at once
+		let frame = self.to_ansi(term_color_support);
+		
+		let mut out = BufWriter::new(stdout().lock());
+		out.write_all(frame.as_bytes()).unwrap();
+		out.flush().unwrap();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn rows(pixels: &Pixels) -> Vec<String> {
+		(0..pixels.size.1).map(|y| (0..pixels.size.0).map(|x| pixels.get_pixel((x, y)).unwrap().0).collect()).collect()
+	}
+	
+	#[test]
+	fn rects() {
+		let white = Color::new_rgb(255, 255, 255);
+		let mut pixels = Pixels::new((5, 4));
+		pixels.draw_rect((1, 0), (3, 3), '#', white);
+		assert_eq!(rows(&pixels), vec![" ### ", " # # ", " ### ", "     "]);
+		
+		// Rectangles partly or completely off the pixels are cut
+		pixels.fill('.', white);
+		pixels.fill_rect((-2, 2), (4, 10), '#', white);
+		pixels.fill_rect((10, 10), (4, 4), '@', white);
+		pixels.draw_rect((3, -1), (5, 2), '+', white);
+		assert_eq!(rows(&pixels), vec!["...++", ".....", "##...", "##..."]);
 	}
-}
\ No newline at end of file
+	
+	#[test]
+	fn vertical_centering() {
+		let white = Color::new_rgb(255, 255, 255);
+		let mut pixels = Pixels::new((1, 4));
+		pixels.print("a\nb", white, false, false, (0, 2), HCentering::Left, VCentering::Middle);
+		assert_eq!(rows(&pixels), vec![" ", "a", "b", " "]);
+		
+		let mut pixels = Pixels::new((1, 5));
+		pixels.print("a\nb\nc", white, false, false, (0, 2), HCentering::Left, VCentering::Middle);
+		assert_eq!(rows(&pixels), vec![" ", "a", "b", "c", " "]);
+		
+		let mut pixels = Pixels::new((1, 3));
+		pixels.print("a", white, false, false, (0, 1), HCentering::Left, VCentering::Middle);
+		assert_eq!(rows(&pixels), vec![" ", "a", " "]);
+	}
+	
+	#[test]
+	fn columns() {
+		let text = ColoredText::new_text("a\nb\nc\nd\ne", Color::new_rgb(255, 255, 255), false, false);
+		let mut pixels = Pixels::new((7, 3));
+		pixels.print_columns(&text, (0, 1), 3, 2);
+		assert_eq!(rows(&pixels), vec!["       ", "a  c  e", "b  d   "]);
+		
+		let mut pixels = Pixels::new((7, 2));
+		pixels.print_columns(&ColoredText::new_text("a\nb", Color::new_rgb(255, 255, 255), false, false), (0, 0), 3, 2);
+		assert_eq!(rows(&pixels), vec!["a      ", "b      "]);
+	}
+	
+	#[test]
+	fn ansi_output() {
+		let red = Color::new_rgb(255, 0, 0);
+		let mut pixels = Pixels::new((5, 1));
+		pixels.print("ab", red, true, false, (0, 0), HCentering::Left, VCentering::Top);
+		pixels.print("c", red, false, false, (2, 0), HCentering::Left, VCentering::Top);
+		// The underline is written once for both cells, and dropping it before c resets the color too
+		assert_eq!(
+			pixels.to_ansi(&TermColorSupport::TrueColor),
+			"\x1b[1;1H\x1b[4m\x1b[38;2;255;0;0mab\x1b[0m\x1b[38;2;255;0;0mc\x1b[38;2;255;255;255m  \x1b[0m"
+		);
+		assert_eq!(pixels.to_ansi(&TermColorSupport::None), "\x1b[1;1H\x1b[4mab\x1b[0mc  \x1b[0m");
+	}
+}
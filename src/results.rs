@@ -0,0 +1,213 @@
+use std::{fs::{self, OpenOptions}, io::Write};
+
+use crossterm::{event::{self, KeyCode, KeyModifiers}, terminal};
+
+use crate::color::{ColoredText, TermColorSupport};
+use crate::game::key_press;
+use crate::pixels::*;
+use crate::stats::Results;
+use crate::theme::Theme;
+
+// Version of the JSON results format, to bump whenever a field is renamed, removed or changes meaning
+pub const SCHEMA_VERSION: u32 = 1;
+
+const GRAPH_HEIGHT: usize = 6; // Rows of the speed graph
+const GRAPH_WIDTH: usize = 40; // Columns of the speed graph, less on narrow terminals
+const WORDS_WIDTH: usize = 60; // Columns of the marked words, less on narrow terminals
+const COMPACT_HEIGHT: u16 = 8; // Terminals with fewer rows get the results on a single line, like a --mini pane
+
+// What the player did on the results screen
+pub enum Choice {
+	Continue,
+	Quit,
+	Key(char), // Any other character, for the caller to handle
+}
+
+// Line graph of the speed over the test, the columns between two seconds are interpolated
+// bottom is the row of the lowest speed, the graph is skipped when it doesn't fit above it
+fn draw_graph(pixels: &mut Pixels, wpms: &[f32], center: usize, bottom: usize, theme: &Theme) {
+	let width = GRAPH_WIDTH.min(pixels.size.0.saturating_sub(12));
+	if wpms.len() < 2 || width < 2 || bottom < GRAPH_HEIGHT {
+		return;
+	}
+	let top = bottom + 1 - GRAPH_HEIGHT;
+	let left = center - width / 2;
+	let min = wpms.iter().cloned().fold(f32::INFINITY, f32::min);
+	let max = wpms.iter().cloned().fold(0.0, f32::max);
+	
+	for x in 0..width {
+		let t = x as f32 * (wpms.len() - 1) as f32 / (width - 1) as f32;
+		let (i, frac) = (t as usize, t.fract());
+		let wpm = wpms[i] + (wpms.get(i + 1).unwrap_or(&wpms[i]) - wpms[i]) * frac;
+		let height = if max > min { (wpm - min) / (max - min) } else { 0.5 };
+		let y = bottom - (height * (GRAPH_HEIGHT - 1) as f32).round() as usize;
+		pixels.set_char((left + x, y), '•');
+		pixels.set_color((left + x, y), theme.caret);
+	}
+	pixels.print(&format!("{:.0}", max), theme.untyped, false, false, (left - 2, top), HCentering::Right, VCentering::Top);
+	pixels.print(&format!("{:.0}", min), theme.untyped, false, false, (left - 2, bottom), HCentering::Right, VCentering::Top);
+}
+
+// Wrapped text from top down to the row before bottom, the lines that don't fit are cut with an ellipsis
+fn draw_words(pixels: &mut Pixels, words: &ColoredText, top: (usize, usize), bottom: usize) {
+	if words.is_empty() || top.1 >= bottom {
+		return;
+	}
+	let mut wrapped = words.clone();
+	wrapped.word_wrap(WORDS_WIDTH.min(pixels.size.0.saturating_sub(4)).max(1));
+	let rows = bottom - top.1;
+	if let Some((cut, _)) = wrapped.chars().iter().enumerate().filter(|(_, c)| **c == '\n').nth(rows - 1) {
+		wrapped = wrapped.slice(0, cut);
+		let color = *wrapped.colors().last().unwrap();
+		wrapped.push('…', color, false, false);
+	}
+	pixels.print_color(&wrapped, top, HCentering::Center, VCentering::Top);
+}
+
+// Show the results of a finished test until a key is pressed
+// options is an extra line drawn under the results, e.g. the modes the player can switch to
+// wpms is the speed at each second of the test, drawn as a graph above the results when there's room
+// words is the text marked word by word, drawn under the results on the rows left, empty for none
+// best is the speed and accuracy of the personal best this run is compared to, see personal_best
+#[allow(clippy::too_many_arguments)]
+pub fn show(title: &str, results: &Results, wpms: &[f32], words: &ColoredText, best: Option<(f32, f32)>, options: Option<&ColoredText>, theme: &Theme, term_color_support: &TermColorSupport) -> Choice {
+	loop {
+		let tsize = terminal::size().unwrap();
+		let mut pixels = Pixels::new((tsize.0 as usize, tsize.1 as usize));
+		pixels.background_all(theme.background);
+		
+		let center = (tsize.0 as usize / 2, tsize.1 as usize / 2);
+		if tsize.1 < COMPACT_HEIGHT {
+			let line = format!("{:.0} wpm  {:.0}% acc  {:.1}s  [enter] continue  [esc] quit", results.wpm(), results.shown_accuracy(), results.time.as_secs_f32());
+			pixels.print(&line, theme.typed, false, false, (center.0, 0), HCentering::Center, VCentering::Top);
+			pixels.render(term_color_support);
+			if let Some(choice) = read_choice(false) {
+				return choice;
+			}
+			continue;
+		}
+		let lines = [
+			format!("{:.0} wpm", results.wpm()),
+			format!("{:.0}% accuracy", results.shown_accuracy()),
+			format!("{:.1}s", results.time.as_secs_f32()),
+		];
+		pixels.print(title, theme.untyped, false, false, (center.0, center.1.saturating_sub(3)), HCentering::Center, VCentering::Top);
+		draw_graph(&mut pixels, wpms, center.0, center.1.saturating_sub(5), theme);
+		for (i, line) in lines.iter().enumerate() {
+			pixels.print(line, theme.typed, false, i == 0, (center.0, (center.1 + i).saturating_sub(1)), HCentering::Center, VCentering::Top);
+		}
+		if !results.is_reliable() {
+			pixels.print("too short for reliable WPM", theme.untyped, false, false, (center.0, center.1 + 3), HCentering::Center, VCentering::Top);
+		}
+		if let Some((best_wpm, best_accuracy)) = best {
+			// Only a run long enough to be trusted can take the best's place
+			let (line, color, bold) = if results.is_reliable() && results.wpm() > best_wpm {
+				(format!("new personal best! +{:.0} wpm", results.wpm() - best_wpm), theme.caret, true)
+			} else {
+				(format!("{:+.0} wpm  {:+.0}% acc  vs best", results.wpm() - best_wpm, results.accuracy() - best_accuracy), theme.untyped, false)
+			};
+			pixels.print(&line, color, false, bold, (center.0, center.1 + 4), HCentering::Center, VCentering::Top);
+		}
+		if let Some(options) = options {
+			pixels.print_color(options, (center.0, center.1 + 5), HCentering::Center, VCentering::Top);
+		}
+		draw_words(&mut pixels, words, (center.0, center.1 + 7), (tsize.1 as usize).saturating_sub(2));
+		pixels.print("[enter] continue  [esc] quit", theme.untyped, false, false, (center.0, (tsize.1 as usize).saturating_sub(1)), HCentering::Center, VCentering::Bottom);
+		pixels.render(term_color_support);
+		
+		if let Some(choice) = read_choice(options.is_some()) {
+			return choice;
+		}
+	}
+}
+
+// Wait for an event on the results screen, None for the ones that don't make a choice
+// Other characters are only a choice when the caller has options for them
+fn read_choice(with_keys: bool) -> Option<Choice> {
+	if let Some(e) = key_press(event::read().unwrap()) {
+		match e.code {
+			KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Tab => return Some(Choice::Continue),
+			KeyCode::Esc => return Some(Choice::Quit),
+			KeyCode::Char('c') if e.modifiers == KeyModifiers::CONTROL => return Some(Choice::Quit),
+			KeyCode::Char(c) if with_keys => return Some(Choice::Key(c)),
+			_ => {}
+		}
+	}
+	None
+}
+
+// Plain text card of the results, for sharing
+pub fn card(mode: &str, language: Option<&str>, results: &Results, date: &str) -> String {
+	let mut fields = vec![("mode", mode.to_string())];
+	if let Some(language) = language {
+		fields.push(("language", language.to_string()));
+	}
+	fields.push(("wpm", format!("{:.0}{}", results.wpm(), if results.is_reliable() { "" } else { " (too short for reliable WPM)" })));
+	fields.push(("accuracy", format!("{:.0}%", results.shown_accuracy())));
+	fields.push(("time", format!("{:.1}s", results.time.as_secs_f32())));
+	fields.push(("date", date.to_string()));
+	
+	let mut card = String::from("ttype results\n-------------\n");
+	for (name, value) in fields {
+		card.push_str(&format!("{:<10}{}\n", name, value));
+	}
+	card
+}
+
+// JSON record of the results, for --json and the history file
+// completed is false for a test the player quit before the end
+pub fn json(mode: &str, language: Option<&str>, results: &Results, completed: bool, date: &str) -> jzon::JsonValue {
+	jzon::object!{
+		schema_version: SCHEMA_VERSION,
+		mode: mode,
+		language: language,
+		wpm: results.wpm(),
+		accuracy: results.accuracy(),
+		time: results.time.as_secs_f32(),
+		reliable: results.is_reliable(),
+		completed: completed,
+		correct_chars: results.correct_chars,
+		keystrokes: results.keystrokes,
+		errors: results.errors,
+		date: date,
+	}
+}
+
+// Add a record to a JSON lines history file, creating it if needed
+pub fn append_history(path: &str, record: &jzon::JsonValue) {
+	let mut file = OpenOptions::new().create(true).append(true).open(path).unwrap_or_else(|_| panic!("Can't open the history file {}.", path));
+	writeln!(file, "{}", record.dump()).unwrap_or_else(|_| panic!("Can't write to the history file {}.", path));
+}
+
+// Speed and accuracy of the fastest finished, reliable run of a mode and language in the history, None without one
+pub fn personal_best(history: &[jzon::JsonValue], mode: &str, language: Option<&str>) -> Option<(f32, f32)> {
+	history.iter()
+		.filter(|r| r["mode"].as_str() == Some(mode) && r["language"].as_str() == language)
+		.filter(|r| r["completed"].as_bool() != Some(false) && r["reliable"].as_bool() != Some(false))
+		.filter_map(|r| Some((r["wpm"].as_f32()?, r["accuracy"].as_f32().unwrap_or(0.0))))
+		.max_by(|a, b| a.0.total_cmp(&b.0))
+}
+
+// Records of a history file, oldest first, empty if it doesn't exist yet
+// Lines that aren't valid JSON, e.g. cut short by a crash, are skipped
+pub fn read_history(path: &str) -> Vec<jzon::JsonValue> {
+	fs::read_to_string(path).unwrap_or_default().lines().filter_map(|line| jzon::parse(line).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn best_of_history() {
+		let history = [
+			jzon::object!{mode: "10 words", language: "english", wpm: 50.0, accuracy: 90.0, completed: true, reliable: true},
+			jzon::object!{mode: "10 words", language: "english", wpm: 70.0, accuracy: 95.0, completed: false, reliable: true},
+			jzon::object!{mode: "10 words", language: "english", wpm: 60.0, accuracy: 97.0, completed: true, reliable: true},
+			jzon::object!{mode: "10 words", language: "french", wpm: 80.0, accuracy: 99.0, completed: true, reliable: true},
+			jzon::object!{mode: "25 words", language: "english", wpm: 90.0, accuracy: 99.0, completed: true, reliable: true},
+		];
+		assert_eq!(personal_best(&history, "10 words", Some("english")), Some((60.0, 97.0)));
+		assert_eq!(personal_best(&history, "quote", Some("english")), None);
+	}
+}
@@ -0,0 +1,388 @@
+use std::{collections::HashMap, time::{Duration, Instant}};
+
+use crate::game::{self, CharStatus};
+
+// Runs shorter than this give wild speeds, a single fast word can be 300 wpm
+const MIN_RELIABLE_TIME: Duration = Duration::from_secs(5);
+
+// Measures of the current run
+#[derive(Default)]
+pub struct Stats {
+	pub started: Option<Instant>, // Time of the first key press
+	pub keystrokes: usize, // Characters typed, corrections excluded
+	pub errors: usize, // Characters typed that didn't match the text
+	pub idle_timeout: Option<Duration>, // Time without key presses after which the timer stops
+	last_key: Option<Instant>,
+	paused: Option<Instant>, // Time the timer was paused at
+	stopped: Duration, // Time left out of the timer because the player was idle or the game paused
+	pub samples: Vec<(Duration, usize)>, // Time of each key press and correct characters after it, for the speed graph
+}
+
+impl Stats {
+	pub fn new(idle_timeout: Option<Duration>) -> Stats {
+		Stats {
+			idle_timeout,
+			..Stats::default()
+		}
+	}
+	
+	// Count a key press given the number of correct characters before and after it
+	pub fn record(&mut self, inserted: bool, correct_before: usize, correct_after: usize) {
+		self.resume();
+		self.started.get_or_insert_with(Instant::now);
+		self.stopped += self.current_idle();
+		self.last_key = Some(Instant::now());
+		self.samples.push((self.elapsed().unwrap_or_default(), correct_after));
+		if inserted {
+			self.keystrokes += 1;
+			if correct_after <= correct_before {
+				self.errors += 1;
+			}
+		}
+	}
+	
+	// Time since the first key press, idle and paused time excluded
+	pub fn elapsed(&self) -> Option<Duration> {
+		let paused = self.paused.map_or(Duration::ZERO, |p| p.elapsed());
+		self.started.map(|t| t.elapsed().saturating_sub(self.stopped + self.current_idle() + paused))
+	}
+	
+	// Stop the timer until resume is called or a key is pressed
+	pub fn pause(&mut self) {
+		if self.started.is_some() && self.paused.is_none() {
+			self.stopped += self.current_idle();
+			self.last_key = None;
+			self.paused = Some(Instant::now());
+		}
+	}
+	
+	pub fn resume(&mut self) {
+		if let Some(paused) = self.paused.take() {
+			self.stopped += paused.elapsed();
+			self.last_key = Some(Instant::now());
+		}
+	}
+	
+	pub fn is_paused(&self) -> bool {
+		self.paused.is_some()
+	}
+	
+	// Time the player has been idle past the timeout since the last key press
+	fn current_idle(&self) -> Duration {
+		match (self.last_key, self.idle_timeout) {
+			(Some(last), Some(timeout)) => last.elapsed().saturating_sub(timeout),
+			_ => Duration::ZERO
+		}
+	}
+	
+	// Whether the timer is stopped until the next key press
+	pub fn is_idle(&self) -> bool {
+		!self.current_idle().is_zero()
+	}
+	
+	// Time left before the player is considered idle
+	pub fn until_idle(&self) -> Option<Duration> {
+		match (self.last_key, self.idle_timeout) {
+			(Some(last), Some(timeout)) if !self.is_idle() => Some(timeout.saturating_sub(last.elapsed())),
+			_ => None
+		}
+	}
+	
+	// Snapshot of the run's results
+	pub fn results(&self, correct_chars: usize) -> Results {
+		Results {
+			correct_chars,
+			keystrokes: self.keystrokes,
+			errors: self.errors,
+			time: self.elapsed().unwrap_or_default(),
+		}
+	}
+}
+
+// Speed at the end of each second of a run, from the correct characters after each key press
+// The last value is the speed at the end of the run, which may not end on a whole second
+pub fn wpm_over_time(samples: &[(Duration, usize)], total: Duration) -> Vec<f32> {
+	let seconds = total.as_secs_f32().ceil() as u64;
+	(1..=seconds).map(|s| {
+		let time = Duration::from_secs(s).min(total);
+		let correct = samples.iter().rev().find(|(t, _)| *t <= time).map_or(0, |&(_, c)| c);
+		correct as f32 / 5.0 * 60.0 / time.as_secs_f32()
+	}).collect()
+}
+
+// Share of right among total in percent, 0 when there is nothing to grade
+fn percentage(right: usize, total: usize) -> f32 {
+	if total == 0 {
+		return 0.0;
+	}
+	right as f32 / total as f32 * 100.0
+}
+
+// Results of one or more runs
+#[derive(Clone, Copy, Default)]
+pub struct Results {
+	pub correct_chars: usize,
+	pub keystrokes: usize,
+	pub errors: usize,
+	pub time: Duration,
+}
+
+impl Results {
+	// Words per minute, a word being 5 correct characters
+	pub fn wpm(&self) -> f32 {
+		let minutes = self.time.as_secs_f32() / 60.0;
+		if minutes > 0.0 {
+			self.correct_chars as f32 / 5.0 / minutes
+		} else {
+			0.0
+		}
+	}
+	
+	// Percentage of key presses that matched the text, see percentage
+	pub fn accuracy(&self) -> f32 {
+		percentage(self.keystrokes.saturating_sub(self.errors), self.keystrokes)
+	}
+	
+	// Accuracy to show, anything short of perfect stays at 99 or under so it can't be rounded up to 100%
+	// The JSON output and the history keep the real one
+	pub fn shown_accuracy(&self) -> f32 {
+		if self.errors > 0 { self.accuracy().min(99.0) } else { self.accuracy() }
+	}
+	
+	// Whether the run was long enough for its speed to mean something
+	pub fn is_reliable(&self) -> bool {
+		self.time >= MIN_RELIABLE_TIME
+	}
+	
+	// Results of several runs taken together
+	pub fn total(results: &[Results]) -> Results {
+		results.iter().fold(Results::default(), |total, r| Results {
+			correct_chars: total.correct_chars + r.correct_chars,
+			keystrokes: total.keystrokes + r.keystrokes,
+			errors: total.errors + r.errors,
+			time: total.time + r.time,
+		})
+	}
+}
+
+// Grade of a typed text, see score
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RunResult {
+	pub wpm: f32,
+	pub raw_wpm: f32,
+	pub accuracy: f32,
+	pub errors: usize,
+}
+
+// Grade a typed text against the text it should match, without any terminal or key history
+//   wpm = correct characters / 5 / minutes, see game::correct_char_count
+//   raw_wpm = typed characters / 5 / minutes, mistakes included
+//   errors = wrong + extra + missing characters
+//   accuracy = correct characters / (correct characters + errors) * 100, 0 when nothing was typed, see percentage
+pub fn score(typed: &str, text: &str, elapsed: Duration) -> RunResult {
+	let statuses = game::char_statuses(typed, text);
+	let correct = game::correct_char_count(&statuses);
+	let errors = statuses.iter().filter(|s| matches!(s, CharStatus::Wrong(_) | CharStatus::Extra(_) | CharStatus::Missing(_))).count();
+	
+	let minutes = elapsed.as_secs_f32() / 60.0;
+	let per_minute = |chars: usize| if minutes > 0.0 { chars as f32 / 5.0 / minutes } else { 0.0 };
+	RunResult {
+		wpm: per_minute(correct),
+		raw_wpm: per_minute(typed.chars().count()),
+		accuracy: percentage(correct, correct + errors),
+		errors,
+	}
+}
+
+// Time needed to type a text at a steady speed, every character counting as a fifth of a word
+pub fn estimated_time(text: &str, wpm: f32) -> Duration {
+	Duration::from_secs_f32(text.trim_end().chars().count() as f32 / 5.0 / wpm * 60.0)
+}
+
+#[derive(Default)]
+struct KeyStat {
+	presses: usize,
+	errors: usize,
+	latency: Duration, // Total time since the previous key press
+	timed: usize, // Presses with a latency, the first one of a run has none
+}
+
+// Accuracy and speed of each key, keyed by the character the text expected
+#[derive(Default)]
+pub struct KeyStats {
+	keys: HashMap<char, KeyStat>,
+	split_case: bool, // Count upper and lower case letters separately
+	last: Option<Instant>,
+}
+
+impl KeyStats {
+	pub fn new(split_case: bool) -> KeyStats {
+		KeyStats {
+			split_case,
+			..KeyStats::default()
+		}
+	}
+	
+	// Forget the last key press so the time between runs isn't counted
+	pub fn start_run(&mut self) {
+		self.last = None;
+	}
+	
+	pub fn record(&mut self, expected: char, typed: char) {
+		let key = if self.split_case { expected } else { expected.to_lowercase().next().unwrap_or(expected) };
+		let stat = self.keys.entry(key).or_default();
+		stat.presses += 1;
+		if typed != expected {
+			stat.errors += 1;
+		}
+		if let Some(last) = self.last {
+			stat.latency += last.elapsed();
+			stat.timed += 1;
+		}
+		self.last = Some(Instant::now());
+	}
+	
+	// Counts of each key as stored in the history, e.g. {"a": {"presses": 12, "errors": 1, "timed": 11, "latency_ms": 2310}}
+	pub fn to_json(&self) -> jzon::JsonValue {
+		let mut keys = jzon::JsonValue::new_object();
+		for (key, stat) in &self.keys {
+			keys[key.to_string().as_str()] = jzon::object!{
+				presses: stat.presses,
+				errors: stat.errors,
+				timed: stat.timed,
+				latency_ms: stat.latency.as_millis() as u64,
+			};
+		}
+		keys
+	}
+	
+	// Add the counts of keys stored by to_json, entries that can't be read are skipped
+	pub fn add_json(&mut self, keys: &jzon::JsonValue) {
+		for (name, counts) in keys.entries() {
+			let mut chars = name.chars();
+			let (Some(key), None) = (chars.next(), chars.next()) else {
+				continue;
+			};
+			let stat = self.keys.entry(key).or_default();
+			stat.presses += counts["presses"].as_usize().unwrap_or(0);
+			stat.errors += counts["errors"].as_usize().unwrap_or(0);
+			stat.timed += counts["timed"].as_usize().unwrap_or(0);
+			stat.latency += Duration::from_millis(counts["latency_ms"].as_u64().unwrap_or(0));
+		}
+	}
+	
+	// Average latency of each key divided by the average latency of all of them, above 1.0 for the slow keys
+	// Empty when no key press was timed yet
+	pub fn slowness(&self) -> HashMap<char, f32> {
+		let timed: usize = self.keys.values().map(|s| s.timed).sum();
+		let total: Duration = self.keys.values().map(|s| s.latency).sum();
+		if timed == 0 || total.is_zero() {
+			return HashMap::new();
+		}
+		let average = total.as_secs_f32() / timed as f32;
+		self.keys.iter()
+			.filter(|(_, s)| s.timed > 0)
+			.map(|(&key, s)| (key, s.latency.as_secs_f32() / s.timed as f32 / average))
+			.collect()
+	}
+	
+	// Table of every key pressed, the least accurate first
+	pub fn report(&self) -> String {
+		let mut keys: Vec<(&char, &KeyStat)> = self.keys.iter().collect();
+		keys.sort_by(|a, b| (a.1.errors * b.1.presses).cmp(&(b.1.errors * a.1.presses)).reverse().then(a.0.cmp(b.0)));
+		
+		let mut report = format!("{:<6}{:>8}{:>10}{:>10}\n", "key", "presses", "accuracy", "latency");
+		for (key, stat) in keys {
+			let name = if *key == ' ' { "space".to_string() } else { key.to_string() };
+			let accuracy = (stat.presses - stat.errors) as f32 / stat.presses as f32 * 100.0;
+			let latency = if stat.timed > 0 { format!("{}ms", (stat.latency / stat.timed as u32).as_millis()) } else { "-".to_string() };
+			report.push_str(&format!("{:<6}{:>8}{:>9.0}%{:>10}\n", name, stat.presses, accuracy, latency));
+		}
+		report
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const MINUTE: Duration = Duration::from_secs(60);
+	
+	#[test]
+	fn score_perfect() {
+		let result = score("the cat sat", "the cat sat", MINUTE);
+		assert_eq!(result, RunResult { wpm: 11.0 / 5.0, raw_wpm: 11.0 / 5.0, accuracy: 100.0, errors: 0 });
+	}
+	
+	#[test]
+	fn score_half_wrong() {
+		// "cat" is all wrong, only "the " counts
+		let result = score("the xyz", "the cat", MINUTE);
+		assert_eq!(result, RunResult { wpm: 4.0 / 5.0, raw_wpm: 7.0 / 5.0, accuracy: 4.0 / 7.0 * 100.0, errors: 3 });
+	}
+	
+	#[test]
+	fn score_overtyped() {
+		let result = score("thee cat", "the cat", MINUTE);
+		assert_eq!(result.errors, 1);
+		assert_eq!(result.wpm, 6.0 / 5.0);
+		assert_eq!(result.raw_wpm, 8.0 / 5.0);
+		assert_eq!(result.accuracy, 600.0 / 7.0);
+	}
+	
+	#[test]
+	fn score_nothing_typed() {
+		assert_eq!(score("", "the cat", MINUTE).accuracy, 0.0);
+		assert_eq!(Results::default().accuracy(), 0.0);
+	}
+	
+	#[test]
+	fn score_all_wrong() {
+		let result = score("xyz", "the", MINUTE);
+		assert_eq!(result.accuracy, 0.0);
+		assert_eq!(result.wpm, 0.0);
+		assert_eq!(Results { keystrokes: 3, errors: 3, ..Results::default() }.accuracy(), 0.0);
+	}
+	
+	#[test]
+	fn accuracy_rounding() {
+		// 999 right out of 1000 would show as 100%
+		let almost = Results { keystrokes: 1000, errors: 1, ..Results::default() };
+		assert_eq!(almost.accuracy(), 99.9);
+		assert_eq!(almost.shown_accuracy(), 99.0);
+		assert_eq!(Results { keystrokes: 1000, errors: 0, ..Results::default() }.shown_accuracy(), 100.0);
+		assert_eq!(Results { keystrokes: 4, errors: 1, ..Results::default() }.shown_accuracy(), 75.0);
+	}
+	
+	#[test]
+	fn score_no_time() {
+		assert_eq!(score("the", "the", Duration::ZERO).wpm, 0.0);
+	}
+	
+	#[test]
+	fn estimate() {
+		assert_eq!(estimated_time("the cat sat on the mat ", 60.0).as_secs(), 4);
+		assert_eq!(estimated_time(&"a".repeat(100), 50.0), Duration::from_secs(24));
+	}
+	
+	#[test]
+	fn speed_over_time() {
+		let samples = [(Duration::ZERO, 1), (Duration::from_millis(500), 5), (Duration::from_millis(1500), 10)];
+		assert_eq!(wpm_over_time(&samples, Duration::from_secs(2)), vec![60.0, 60.0]);
+		assert_eq!(wpm_over_time(&samples, Duration::from_millis(1500)), vec![60.0, 80.0]);
+		assert!(wpm_over_time(&[], Duration::ZERO).is_empty());
+	}
+	
+	#[test]
+	fn key_slowness() {
+		let mut keys = KeyStats::new(false);
+		assert!(keys.slowness().is_empty());
+		
+		keys.add_json(&jzon::parse(r#"{"a": {"presses": 2, "timed": 2, "latency_ms": 200}, "b": {"presses": 1, "timed": 1, "latency_ms": 400}, "long": {}}"#).unwrap());
+		keys.add_json(&keys.to_json());
+		let slowness = keys.slowness();
+		assert_eq!(slowness.len(), 2);
+		assert!((slowness[&'a'] - 0.5).abs() < 0.001);
+		assert!((slowness[&'b'] - 2.0).abs() < 0.001);
+	}
+}
@@ -4,259 +4,259 @@ use crate::color::Color;
 
 pub const TERM_COLORS: [Color; 256] = [
     Color::new_rgb(0, 0, 0),
-    Color::new_rgb(8, 0, 0),
-    Color::new_rgb(0, 8, 0),
-    Color::new_rgb(8, 8, 0),
-    Color::new_rgb(0, 0, 8),
-    Color::new_rgb(8, 0, 8),
-    Color::new_rgb(0, 8, 8),
-    Color::new_rgb(12, 12, 12),
-    Color::new_rgb(8, 8, 8),
-    Color::new_rgb(15, 0, 0),
-    Color::new_rgb(0, 15, 0),
-    Color::new_rgb(15, 15, 0),
-    Color::new_rgb(0, 0, 15),
-    Color::new_rgb(15, 0, 15),
-    Color::new_rgb(0, 15, 15),
-    Color::new_rgb(15, 15, 15),
-    Color::new_rgb(0, 0, 0),
-    Color::new_rgb(0, 0, 5),
-    Color::new_rgb(0, 0, 8),
-    Color::new_rgb(0, 0, 10),
-    Color::new_rgb(0, 0, 13),
-    Color::new_rgb(0, 0, 15),
-    Color::new_rgb(0, 5, 0),
-    Color::new_rgb(0, 5, 5),
-    Color::new_rgb(0, 5, 8),
-    Color::new_rgb(0, 5, 10),
-    Color::new_rgb(0, 5, 13),
-    Color::new_rgb(0, 5, 15),
-    Color::new_rgb(0, 8, 0),
-    Color::new_rgb(0, 8, 5),
-    Color::new_rgb(0, 8, 8),
-    Color::new_rgb(0, 8, 10),
-    Color::new_rgb(0, 8, 13),
-    Color::new_rgb(0, 8, 15),
-    Color::new_rgb(0, 10, 0),
-    Color::new_rgb(0, 10, 5),
-    Color::new_rgb(0, 10, 8),
-    Color::new_rgb(0, 10, 10),
-    Color::new_rgb(0, 10, 13),
-    Color::new_rgb(0, 10, 15),
-    Color::new_rgb(0, 13, 0),
-    Color::new_rgb(0, 13, 5),
-    Color::new_rgb(0, 13, 8),
-    Color::new_rgb(0, 13, 10),
-    Color::new_rgb(0, 13, 13),
-    Color::new_rgb(0, 13, 15),
-    Color::new_rgb(0, 15, 0),
-    Color::new_rgb(0, 15, 5),
-    Color::new_rgb(0, 15, 8),
-    Color::new_rgb(0, 15, 10),
-    Color::new_rgb(0, 15, 13),
-    Color::new_rgb(0, 15, 15),
-    Color::new_rgb(5, 0, 0),
-    Color::new_rgb(5, 0, 5),
-    Color::new_rgb(5, 0, 8),
-    Color::new_rgb(5, 0, 10),
-    Color::new_rgb(5, 0, 13),
-    Color::new_rgb(5, 0, 15),
-    Color::new_rgb(5, 5, 0),
-    Color::new_rgb(5, 5, 5),
-    Color::new_rgb(5, 5, 8),
-    Color::new_rgb(5, 5, 10),
-    Color::new_rgb(5, 5, 13),
-    Color::new_rgb(5, 5, 15),
-    Color::new_rgb(5, 8, 0),
-    Color::new_rgb(5, 8, 5),
-    Color::new_rgb(5, 8, 8),
-    Color::new_rgb(5, 8, 10),
-    Color::new_rgb(5, 8, 13),
-    Color::new_rgb(5, 8, 15),
-    Color::new_rgb(5, 10, 0),
-    Color::new_rgb(5, 10, 5),
-    Color::new_rgb(5, 10, 8),
-    Color::new_rgb(5, 10, 10),
-    Color::new_rgb(5, 10, 13),
-    Color::new_rgb(5, 10, 15),
-    Color::new_rgb(5, 13, 0),
-    Color::new_rgb(5, 13, 5),
-    Color::new_rgb(5, 13, 8),
-    Color::new_rgb(5, 13, 10),
-    Color::new_rgb(5, 13, 13),
-    Color::new_rgb(5, 13, 15),
-    Color::new_rgb(5, 15, 0),
-    Color::new_rgb(5, 15, 5),
-    Color::new_rgb(5, 15, 8),
-    Color::new_rgb(5, 15, 10),
-    Color::new_rgb(5, 15, 13),
-    Color::new_rgb(5, 15, 15),
-    Color::new_rgb(8, 0, 0),
-    Color::new_rgb(8, 0, 5),
-    Color::new_rgb(8, 0, 8),
-    Color::new_rgb(8, 0, 10),
-    Color::new_rgb(8, 0, 13),
-    Color::new_rgb(8, 0, 15),
-    Color::new_rgb(8, 5, 0),
-    Color::new_rgb(8, 5, 5),
-    Color::new_rgb(8, 5, 8),
-    Color::new_rgb(8, 5, 10),
-    Color::new_rgb(8, 5, 13),
-    Color::new_rgb(8, 5, 15),
-    Color::new_rgb(8, 8, 0),
-    Color::new_rgb(8, 8, 5),
-    Color::new_rgb(8, 8, 8),
-    Color::new_rgb(8, 8, 10),
-    Color::new_rgb(8, 8, 13),
-    Color::new_rgb(8, 8, 15),
-    Color::new_rgb(8, 10, 0),
-    Color::new_rgb(8, 10, 5),
-    Color::new_rgb(8, 10, 8),
-    Color::new_rgb(8, 10, 10),
-    Color::new_rgb(8, 10, 13),
-    Color::new_rgb(8, 10, 15),
-    Color::new_rgb(8, 13, 0),
-    Color::new_rgb(8, 13, 5),
-    Color::new_rgb(8, 13, 8),
-    Color::new_rgb(8, 13, 10),
-    Color::new_rgb(8, 13, 13),
-    Color::new_rgb(8, 13, 15),
-    Color::new_rgb(8, 15, 0),
-    Color::new_rgb(8, 15, 5),
-    Color::new_rgb(8, 15, 8),
-    Color::new_rgb(8, 15, 10),
-    Color::new_rgb(8, 15, 13),
-    Color::new_rgb(8, 15, 15),
-    Color::new_rgb(10, 0, 0),
-    Color::new_rgb(10, 0, 5),
-    Color::new_rgb(10, 0, 8),
-    Color::new_rgb(10, 0, 10),
-    Color::new_rgb(10, 0, 13),
-    Color::new_rgb(10, 0, 15),
-    Color::new_rgb(10, 5, 0),
-    Color::new_rgb(10, 5, 5),
-    Color::new_rgb(10, 5, 8),
-    Color::new_rgb(10, 5, 10),
-    Color::new_rgb(10, 5, 13),
-    Color::new_rgb(10, 5, 15),
-    Color::new_rgb(10, 8, 0),
-    Color::new_rgb(10, 8, 5),
-    Color::new_rgb(10, 8, 8),
-    Color::new_rgb(10, 8, 10),
-    Color::new_rgb(10, 8, 13),
-    Color::new_rgb(10, 8, 15),
-    Color::new_rgb(10, 10, 0),
-    Color::new_rgb(10, 10, 5),
-    Color::new_rgb(10, 10, 8),
-    Color::new_rgb(10, 10, 10),
-    Color::new_rgb(10, 10, 13),
-    Color::new_rgb(10, 10, 15),
-    Color::new_rgb(10, 13, 0),
-    Color::new_rgb(10, 13, 5),
-    Color::new_rgb(10, 13, 8),
-    Color::new_rgb(10, 13, 10),
-    Color::new_rgb(10, 13, 13),
-    Color::new_rgb(10, 13, 15),
-    Color::new_rgb(10, 15, 0),
-    Color::new_rgb(10, 15, 5),
-    Color::new_rgb(10, 15, 8),
-    Color::new_rgb(10, 15, 10),
-    Color::new_rgb(10, 15, 13),
-    Color::new_rgb(10, 15, 15),
-    Color::new_rgb(13, 0, 0),
-    Color::new_rgb(13, 0, 5),
-    Color::new_rgb(13, 0, 8),
-    Color::new_rgb(13, 0, 10),
-    Color::new_rgb(13, 0, 13),
-    Color::new_rgb(13, 0, 15),
-    Color::new_rgb(13, 5, 0),
-    Color::new_rgb(13, 5, 5),
-    Color::new_rgb(13, 5, 8),
-    Color::new_rgb(13, 5, 10),
-    Color::new_rgb(13, 5, 13),
-    Color::new_rgb(13, 5, 15),
-    Color::new_rgb(13, 8, 0),
-    Color::new_rgb(13, 8, 5),
-    Color::new_rgb(13, 8, 8),
-    Color::new_rgb(13, 8, 10),
-    Color::new_rgb(13, 8, 13),
-    Color::new_rgb(13, 8, 15),
-    Color::new_rgb(13, 10, 0),
-    Color::new_rgb(13, 10, 5),
-    Color::new_rgb(13, 10, 8),
-    Color::new_rgb(13, 10, 10),
-    Color::new_rgb(13, 10, 13),
-    Color::new_rgb(13, 10, 15),
-    Color::new_rgb(13, 13, 0),
-    Color::new_rgb(13, 13, 5),
-    Color::new_rgb(13, 13, 8),
-    Color::new_rgb(13, 13, 10),
-    Color::new_rgb(13, 13, 13),
-    Color::new_rgb(13, 13, 15),
-    Color::new_rgb(13, 15, 0),
-    Color::new_rgb(13, 15, 5),
-    Color::new_rgb(13, 15, 8),
-    Color::new_rgb(13, 15, 10),
-    Color::new_rgb(13, 15, 13),
-    Color::new_rgb(13, 15, 15),
-    Color::new_rgb(15, 0, 0),
-    Color::new_rgb(15, 0, 5),
-    Color::new_rgb(15, 0, 8),
-    Color::new_rgb(15, 0, 10),
-    Color::new_rgb(15, 0, 13),
-    Color::new_rgb(15, 0, 15),
-    Color::new_rgb(15, 5, 0),
-    Color::new_rgb(15, 5, 5),
-    Color::new_rgb(15, 5, 8),
-    Color::new_rgb(15, 5, 10),
-    Color::new_rgb(15, 5, 13),
-    Color::new_rgb(15, 5, 15),
-    Color::new_rgb(15, 8, 0),
-    Color::new_rgb(15, 8, 5),
-    Color::new_rgb(15, 8, 8),
-    Color::new_rgb(15, 8, 10),
-    Color::new_rgb(15, 8, 13),
-    Color::new_rgb(15, 8, 15),
-    Color::new_rgb(15, 10, 0),
-    Color::new_rgb(15, 10, 5),
-    Color::new_rgb(15, 10, 8),
-    Color::new_rgb(15, 10, 10),
-    Color::new_rgb(15, 10, 13),
-    Color::new_rgb(15, 10, 15),
-    Color::new_rgb(15, 13, 0),
-    Color::new_rgb(15, 13, 5),
-    Color::new_rgb(15, 13, 8),
-    Color::new_rgb(15, 13, 10),
-    Color::new_rgb(15, 13, 13),
-    Color::new_rgb(15, 13, 15),
-    Color::new_rgb(15, 15, 0),
-    Color::new_rgb(15, 15, 5),
-    Color::new_rgb(15, 15, 8),
-    Color::new_rgb(15, 15, 10),
-    Color::new_rgb(15, 15, 13),
-    Color::new_rgb(15, 15, 15),
+    Color::new_rgb(128, 0, 0),
+    Color::new_rgb(0, 128, 0),
+    Color::new_rgb(128, 128, 0),
+    Color::new_rgb(0, 0, 128),
+    Color::new_rgb(128, 0, 128),
+    Color::new_rgb(0, 128, 128),
+    Color::new_rgb(192, 192, 192),
+    Color::new_rgb(128, 128, 128),
+    Color::new_rgb(255, 0, 0),
+    Color::new_rgb(0, 255, 0),
+    Color::new_rgb(255, 255, 0),
+    Color::new_rgb(0, 0, 255),
+    Color::new_rgb(255, 0, 255),
+    Color::new_rgb(0, 255, 255),
+    Color::new_rgb(255, 255, 255),
     Color::new_rgb(0, 0, 0),
-    Color::new_rgb(1, 1, 1),
-    Color::new_rgb(1, 1, 1),
-    Color::new_rgb(2, 2, 2),
-    Color::new_rgb(3, 3, 3),
-    Color::new_rgb(3, 3, 3),
-    Color::new_rgb(4, 4, 4),
-    Color::new_rgb(4, 4, 4),
-    Color::new_rgb(5, 5, 5),
-    Color::new_rgb(6, 6, 6),
-    Color::new_rgb(6, 6, 6),
-    Color::new_rgb(7, 7, 7),
-    Color::new_rgb(8, 8, 8),
+    Color::new_rgb(0, 0, 95),
+    Color::new_rgb(0, 0, 135),
+    Color::new_rgb(0, 0, 175),
+    Color::new_rgb(0, 0, 215),
+    Color::new_rgb(0, 0, 255),
+    Color::new_rgb(0, 95, 0),
+    Color::new_rgb(0, 95, 95),
+    Color::new_rgb(0, 95, 135),
+    Color::new_rgb(0, 95, 175),
+    Color::new_rgb(0, 95, 215),
+    Color::new_rgb(0, 95, 255),
+    Color::new_rgb(0, 135, 0),
+    Color::new_rgb(0, 135, 95),
+    Color::new_rgb(0, 135, 135),
+    Color::new_rgb(0, 135, 175),
+    Color::new_rgb(0, 135, 215),
+    Color::new_rgb(0, 135, 255),
+    Color::new_rgb(0, 175, 0),
+    Color::new_rgb(0, 175, 95),
+    Color::new_rgb(0, 175, 135),
+    Color::new_rgb(0, 175, 175),
+    Color::new_rgb(0, 175, 215),
+    Color::new_rgb(0, 175, 255),
+    Color::new_rgb(0, 215, 0),
+    Color::new_rgb(0, 215, 95),
+    Color::new_rgb(0, 215, 135),
+    Color::new_rgb(0, 215, 175),
+    Color::new_rgb(0, 215, 215),
+    Color::new_rgb(0, 215, 255),
+    Color::new_rgb(0, 255, 0),
+    Color::new_rgb(0, 255, 95),
+    Color::new_rgb(0, 255, 135),
+    Color::new_rgb(0, 255, 175),
+    Color::new_rgb(0, 255, 215),
+    Color::new_rgb(0, 255, 255),
+    Color::new_rgb(95, 0, 0),
+    Color::new_rgb(95, 0, 95),
+    Color::new_rgb(95, 0, 135),
+    Color::new_rgb(95, 0, 175),
+    Color::new_rgb(95, 0, 215),
+    Color::new_rgb(95, 0, 255),
+    Color::new_rgb(95, 95, 0),
+    Color::new_rgb(95, 95, 95),
+    Color::new_rgb(95, 95, 135),
+    Color::new_rgb(95, 95, 175),
+    Color::new_rgb(95, 95, 215),
+    Color::new_rgb(95, 95, 255),
+    Color::new_rgb(95, 135, 0),
+    Color::new_rgb(95, 135, 95),
+    Color::new_rgb(95, 135, 135),
+    Color::new_rgb(95, 135, 175),
+    Color::new_rgb(95, 135, 215),
+    Color::new_rgb(95, 135, 255),
+    Color::new_rgb(95, 175, 0),
+    Color::new_rgb(95, 175, 95),
+    Color::new_rgb(95, 175, 135),
+    Color::new_rgb(95, 175, 175),
+    Color::new_rgb(95, 175, 215),
+    Color::new_rgb(95, 175, 255),
+    Color::new_rgb(95, 215, 0),
+    Color::new_rgb(95, 215, 95),
+    Color::new_rgb(95, 215, 135),
+    Color::new_rgb(95, 215, 175),
+    Color::new_rgb(95, 215, 215),
+    Color::new_rgb(95, 215, 255),
+    Color::new_rgb(95, 255, 0),
+    Color::new_rgb(95, 255, 95),
+    Color::new_rgb(95, 255, 135),
+    Color::new_rgb(95, 255, 175),
+    Color::new_rgb(95, 255, 215),
+    Color::new_rgb(95, 255, 255),
+    Color::new_rgb(135, 0, 0),
+    Color::new_rgb(135, 0, 95),
+    Color::new_rgb(135, 0, 135),
+    Color::new_rgb(135, 0, 175),
+    Color::new_rgb(135, 0, 215),
+    Color::new_rgb(135, 0, 255),
+    Color::new_rgb(135, 95, 0),
+    Color::new_rgb(135, 95, 95),
+    Color::new_rgb(135, 95, 135),
+    Color::new_rgb(135, 95, 175),
+    Color::new_rgb(135, 95, 215),
+    Color::new_rgb(135, 95, 255),
+    Color::new_rgb(135, 135, 0),
+    Color::new_rgb(135, 135, 95),
+    Color::new_rgb(135, 135, 135),
+    Color::new_rgb(135, 135, 175),
+    Color::new_rgb(135, 135, 215),
+    Color::new_rgb(135, 135, 255),
+    Color::new_rgb(135, 175, 0),
+    Color::new_rgb(135, 175, 95),
+    Color::new_rgb(135, 175, 135),
+    Color::new_rgb(135, 175, 175),
+    Color::new_rgb(135, 175, 215),
+    Color::new_rgb(135, 175, 255),
+    Color::new_rgb(135, 215, 0),
+    Color::new_rgb(135, 215, 95),
+    Color::new_rgb(135, 215, 135),
+    Color::new_rgb(135, 215, 175),
+    Color::new_rgb(135, 215, 215),
+    Color::new_rgb(135, 215, 255),
+    Color::new_rgb(135, 255, 0),
+    Color::new_rgb(135, 255, 95),
+    Color::new_rgb(135, 255, 135),
+    Color::new_rgb(135, 255, 175),
+    Color::new_rgb(135, 255, 215),
+    Color::new_rgb(135, 255, 255),
+    Color::new_rgb(175, 0, 0),
+    Color::new_rgb(175, 0, 95),
+    Color::new_rgb(175, 0, 135),
+    Color::new_rgb(175, 0, 175),
+    Color::new_rgb(175, 0, 215),
+    Color::new_rgb(175, 0, 255),
+    Color::new_rgb(175, 95, 0),
+    Color::new_rgb(175, 95, 95),
+    Color::new_rgb(175, 95, 135),
+    Color::new_rgb(175, 95, 175),
+    Color::new_rgb(175, 95, 215),
+    Color::new_rgb(175, 95, 255),
+    Color::new_rgb(175, 135, 0),
+    Color::new_rgb(175, 135, 95),
+    Color::new_rgb(175, 135, 135),
+    Color::new_rgb(175, 135, 175),
+    Color::new_rgb(175, 135, 215),
+    Color::new_rgb(175, 135, 255),
+    Color::new_rgb(175, 175, 0),
+    Color::new_rgb(175, 175, 95),
+    Color::new_rgb(175, 175, 135),
+    Color::new_rgb(175, 175, 175),
+    Color::new_rgb(175, 175, 215),
+    Color::new_rgb(175, 175, 255),
+    Color::new_rgb(175, 215, 0),
+    Color::new_rgb(175, 215, 95),
+    Color::new_rgb(175, 215, 135),
+    Color::new_rgb(175, 215, 175),
+    Color::new_rgb(175, 215, 215),
+    Color::new_rgb(175, 215, 255),
+    Color::new_rgb(175, 255, 0),
+    Color::new_rgb(175, 255, 95),
+    Color::new_rgb(175, 255, 135),
+    Color::new_rgb(175, 255, 175),
+    Color::new_rgb(175, 255, 215),
+    Color::new_rgb(175, 255, 255),
+    Color::new_rgb(215, 0, 0),
+    Color::new_rgb(215, 0, 95),
+    Color::new_rgb(215, 0, 135),
+    Color::new_rgb(215, 0, 175),
+    Color::new_rgb(215, 0, 215),
+    Color::new_rgb(215, 0, 255),
+    Color::new_rgb(215, 95, 0),
+    Color::new_rgb(215, 95, 95),
+    Color::new_rgb(215, 95, 135),
+    Color::new_rgb(215, 95, 175),
+    Color::new_rgb(215, 95, 215),
+    Color::new_rgb(215, 95, 255),
+    Color::new_rgb(215, 135, 0),
+    Color::new_rgb(215, 135, 95),
+    Color::new_rgb(215, 135, 135),
+    Color::new_rgb(215, 135, 175),
+    Color::new_rgb(215, 135, 215),
+    Color::new_rgb(215, 135, 255),
+    Color::new_rgb(215, 175, 0),
+    Color::new_rgb(215, 175, 95),
+    Color::new_rgb(215, 175, 135),
+    Color::new_rgb(215, 175, 175),
+    Color::new_rgb(215, 175, 215),
+    Color::new_rgb(215, 175, 255),
+    Color::new_rgb(215, 215, 0),
+    Color::new_rgb(215, 215, 95),
+    Color::new_rgb(215, 215, 135),
+    Color::new_rgb(215, 215, 175),
+    Color::new_rgb(215, 215, 215),
+    Color::new_rgb(215, 215, 255),
+    Color::new_rgb(215, 255, 0),
+    Color::new_rgb(215, 255, 95),
+    Color::new_rgb(215, 255, 135),
+    Color::new_rgb(215, 255, 175),
+    Color::new_rgb(215, 255, 215),
+    Color::new_rgb(215, 255, 255),
+    Color::new_rgb(255, 0, 0),
+    Color::new_rgb(255, 0, 95),
+    Color::new_rgb(255, 0, 135),
+    Color::new_rgb(255, 0, 175),
+    Color::new_rgb(255, 0, 215),
+    Color::new_rgb(255, 0, 255),
+    Color::new_rgb(255, 95, 0),
+    Color::new_rgb(255, 95, 95),
+    Color::new_rgb(255, 95, 135),
+    Color::new_rgb(255, 95, 175),
+    Color::new_rgb(255, 95, 215),
+    Color::new_rgb(255, 95, 255),
+    Color::new_rgb(255, 135, 0),
+    Color::new_rgb(255, 135, 95),
+    Color::new_rgb(255, 135, 135),
+    Color::new_rgb(255, 135, 175),
+    Color::new_rgb(255, 135, 215),
+    Color::new_rgb(255, 135, 255),
+    Color::new_rgb(255, 175, 0),
+    Color::new_rgb(255, 175, 95),
+    Color::new_rgb(255, 175, 135),
+    Color::new_rgb(255, 175, 175),
+    Color::new_rgb(255, 175, 215),
+    Color::new_rgb(255, 175, 255),
+    Color::new_rgb(255, 215, 0),
+    Color::new_rgb(255, 215, 95),
+    Color::new_rgb(255, 215, 135),
+    Color::new_rgb(255, 215, 175),
+    Color::new_rgb(255, 215, 215),
+    Color::new_rgb(255, 215, 255),
+    Color::new_rgb(255, 255, 0),
+    Color::new_rgb(255, 255, 95),
+    Color::new_rgb(255, 255, 135),
+    Color::new_rgb(255, 255, 175),
+    Color::new_rgb(255, 255, 215),
+    Color::new_rgb(255, 255, 255),
     Color::new_rgb(8, 8, 8),
-    Color::new_rgb(9, 9, 9),
-    Color::new_rgb(9, 9, 9),
-    Color::new_rgb(10, 10, 10),
-    Color::new_rgb(11, 11, 11),
-    Color::new_rgb(11, 11, 11),
-    Color::new_rgb(12, 12, 12),
-    Color::new_rgb(13, 13, 13),
-    Color::new_rgb(13, 13, 13),
-    Color::new_rgb(14, 14, 14),
-    Color::new_rgb(14, 14, 14),
+    Color::new_rgb(18, 18, 18),
+    Color::new_rgb(28, 28, 28),
+    Color::new_rgb(38, 38, 38),
+    Color::new_rgb(48, 48, 48),
+    Color::new_rgb(58, 58, 58),
+    Color::new_rgb(68, 68, 68),
+    Color::new_rgb(78, 78, 78),
+    Color::new_rgb(88, 88, 88),
+    Color::new_rgb(98, 98, 98),
+    Color::new_rgb(108, 108, 108),
+    Color::new_rgb(118, 118, 118),
+    Color::new_rgb(128, 128, 128),
+    Color::new_rgb(138, 138, 138),
+    Color::new_rgb(148, 148, 148),
+    Color::new_rgb(158, 158, 158),
+    Color::new_rgb(168, 168, 168),
+    Color::new_rgb(178, 178, 178),
+    Color::new_rgb(188, 188, 188),
+    Color::new_rgb(198, 198, 198),
+    Color::new_rgb(208, 208, 208),
+    Color::new_rgb(218, 218, 218),
+    Color::new_rgb(228, 228, 228),
+    Color::new_rgb(238, 238, 238),
 ];
\ No newline at end of file
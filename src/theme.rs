@@ -0,0 +1,49 @@
+use crate::color::Color;
+
+// Colors used to draw the game
+pub struct Theme {
+	pub untyped: Color,
+	pub typed: Color,
+	pub wrong: Color, // Character typed instead of another
+	pub extra: Color, // Character typed past the end of a word
+	pub missing: Color, // Character skipped in a word the player moved past
+	pub caret: Color,
+	pub pace: Color, // Ghost caret of --pace
+	pub background: Option<Color>, // None keeps the terminal's background
+}
+
+impl Default for Theme {
+	fn default() -> Theme {
+		Theme {
+			untyped: Color::new_rgb(80, 80, 80),
+			typed: Color::new_rgb(255, 255, 255),
+			wrong: Color::new_rgb(255, 0, 0),
+			extra: Color::new_rgb(255, 0, 0),
+			missing: Color::new_rgb(80, 80, 80),
+			caret: Color::new_rgb(255, 190, 0),
+			pace: Color::new_rgb(90, 130, 200),
+			background: None,
+		}
+	}
+}
+
+impl Theme {
+	// Get one of the built-in themes by name
+	pub fn from_name(name: &str) -> Option<Theme> {
+		match name {
+			"default" => Some(Theme::default()),
+			// Designed for low vision, every color stays distinct when reduced to 16 colors
+			"high-contrast" => Some(Theme {
+				untyped: Color::new_rgb(170, 170, 170),
+				typed: Color::new_rgb(255, 255, 255),
+				wrong: Color::new_rgb(255, 0, 0),
+				extra: Color::new_rgb(255, 0, 0),
+				missing: Color::new_rgb(170, 170, 170),
+				caret: Color::new_rgb(255, 255, 0),
+				pace: Color::new_rgb(0, 255, 255),
+				background: Some(Color::new_rgb(0, 0, 0)),
+			}),
+			_ => None
+		}
+	}
+}
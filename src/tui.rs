@@ -0,0 +1,41 @@
+use std::{io::stdout, sync::atomic::{AtomicBool, Ordering}};
+
+use crossterm::{cursor, event, execute, style::Print, terminal};
+
+const PUSH_TITLE: &str = "\x1b[22;0t";
+const POP_TITLE: &str = "\x1b[23;0t";
+
+static ACTIVE: AtomicBool = AtomicBool::new(false); // Whether the terminal is in the modes of enter_tui
+static TITLE_PUSHED: AtomicBool = AtomicBool::new(false);
+
+// Whether enter_tui set up the terminal and leave_tui didn't put it back yet
+pub fn is_active() -> bool {
+	ACTIVE.load(Ordering::SeqCst)
+}
+
+// Switch the terminal to the modes of the tests, every mode turned on here is turned off by leave_tui
+// set_title saves the title of the terminal so it can be changed during the tests
+pub fn enter_tui(set_title: bool) {
+	let mut stdout = stdout();
+	ACTIVE.store(true, Ordering::SeqCst);
+	terminal::enable_raw_mode().unwrap();
+	execute!(stdout, cursor::Hide, terminal::DisableLineWrap, terminal::EnterAlternateScreen, event::EnableFocusChange, event::EnableBracketedPaste).unwrap();
+	if set_title {
+		TITLE_PUSHED.store(true, Ordering::SeqCst);
+		execute!(stdout, Print(PUSH_TITLE)).unwrap();
+	}
+}
+
+// Put the terminal back as it was, on exit and in the panic hook, nothing is written if it wasn't set up
+// Errors are ignored, a panic while panicking would abort and leave the terminal messed up
+pub fn leave_tui() {
+	if !ACTIVE.swap(false, Ordering::SeqCst) {
+		return;
+	}
+	let mut stdout = stdout();
+	let _ = terminal::disable_raw_mode();
+	let _ = execute!(stdout, terminal::EnableLineWrap, terminal::LeaveAlternateScreen, cursor::Show, event::DisableFocusChange, event::DisableBracketedPaste);
+	if TITLE_PUSHED.swap(false, Ordering::SeqCst) {
+		let _ = execute!(stdout, Print(POP_TITLE));
+	}
+}
@@ -1,7 +1,49 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 pub fn nth_char_idx(text: &str, idx: usize) -> usize {
 	text
 		.char_indices()
 		.nth(idx)
 		.expect("Invalid cursor position")
 		.0
-}
\ No newline at end of file
+}
+
+// Whether a character takes up a cell when printed, which control and zero-width characters don't
+pub fn is_printable(c: char) -> bool {
+	!c.is_control() && !matches!(c, '\u{00AD}' | '\u{200B}'..='\u{200F}' | '\u{2060}'..='\u{2064}' | '\u{FEFF}')
+}
+
+// Text of a file without its byte order mark, with Windows and old Mac line endings turned into \n
+pub fn normalize_file_text(text: &str) -> String {
+	text.strip_prefix('\u{FEFF}').unwrap_or(text).replace("\r\n", "\n").replace('\r', "\n")
+}
+
+// Date and time in UTC, as YYYY-MM-DD HH:MM UTC
+pub fn format_date(time: SystemTime) -> String {
+	let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+	let (days, rem) = (secs / 86400, secs % 86400);
+	
+	// Civil date from the number of days since 1970-01-01 (http://howardhinnant.github.io/date_algorithms.html#civil_from_days)
+	let z = days as i64 + 719468;
+	let era = z.div_euclid(146097);
+	let doe = z - era * 146097;
+	let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = doy - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+	
+	format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, rem / 3600, rem % 3600 / 60)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn crlf_and_bom() {
+		assert_eq!(normalize_file_text("\u{FEFF}the cat\r\nsat\rdown\r\n"), "the cat\nsat\ndown\n");
+		assert_eq!(normalize_file_text("the\ncat"), "the\ncat");
+	}
+}